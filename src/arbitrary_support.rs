@@ -0,0 +1,42 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Json, Map};
+
+// Deepest nesting a generated document can have, keeps the generator's own recursion shallow
+const MAX_DEPTH: usize = 8;
+
+// Longest array or object generated on one level
+const MAX_LEN: usize = 8;
+
+// Non-finite numbers have no JSON text, they are generated as 0 so every document round trips
+fn arbitrary_json(u: &mut Unstructured<'_>, depth: usize) -> Result<Json> {
+    let variants = if depth < MAX_DEPTH { 7 } else { 5 };
+    Ok(match u.choose_index(variants)? {
+        0 => Json::Null,
+        1 => Json::Boolean(u.arbitrary()?),
+        2 => {
+            let n: f64 = u.arbitrary()?;
+            Json::Number(if n.is_finite() { n } else { 0.0 })
+        },
+        3 => Json::Integer(u.arbitrary()?),
+        4 => Json::String(u.arbitrary()?),
+        5 => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            Json::Array((0..len).map(|_| arbitrary_json(u, depth + 1)).collect::<Result<_>>()?)
+        },
+        _ => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            let mut members = Map::default();
+            for _ in 0..len {
+                members.insert(u.arbitrary()?, arbitrary_json(u, depth + 1)?);
+            }
+            Json::Object(members)
+        }
+    })
+}
+
+impl<'a> Arbitrary<'a> for Json {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_json(u, 0)
+    }
+}
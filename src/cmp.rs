@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{Json, Map};
+
+// Position of a variant in the ordering across types, both number variants share one rank
+fn type_rank(value: &Json) -> u8 {
+    match value {
+        Json::Null => 0,
+        Json::Boolean(_) => 1,
+        Json::Number(_) | Json::Integer(_) => 2,
+        Json::String(_) => 3,
+        Json::Array(_) => 4,
+        Json::Object(_) => 5
+    }
+}
+
+// Exact comparison of an i64 with an f64, converting either side could round
+fn cmp_integer_number(i: i64, n: f64) -> Option<Ordering> {
+    if n.is_nan() {
+        None
+    } else if n >= 9223372036854775808.0 {
+        Some(Ordering::Less)
+    } else if n < -9223372036854775808.0 {
+        Some(Ordering::Greater)
+    } else {
+        Some(i.cmp(&(n.trunc() as i64)).then(0.0.partial_cmp(&n.fract())?))
+    }
+}
+
+// Structural equality, except that the two number variants are equal when they hold the same
+// value: Integer(1) == Number(1.0). The comparison is exact, so an i64 beyond 2^53 only equals
+// a float that is precisely that integer. Unlike for f64 a NaN Number equals any other NaN Number,
+// so == is an equivalence relation and agrees with Hash and Ord
+impl PartialEq for Json {
+    fn eq(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Null, Json::Null) => true,
+            (Json::Boolean(a), Json::Boolean(b)) => a == b,
+            (Json::Number(a), Json::Number(b)) => a == b || a.is_nan() && b.is_nan(),
+            (Json::Integer(a), Json::Integer(b)) => a == b,
+            (Json::Integer(a), Json::Number(b)) | (Json::Number(b), Json::Integer(a)) => cmp_integer_number(*a, *b) == Some(Ordering::Equal),
+            (Json::String(a), Json::String(b)) => a == b,
+            (Json::Array(a), Json::Array(b)) => a == b,
+            (Json::Object(a), Json::Object(b)) => a == b,
+            _ => false
+        }
+    }
+}
+
+impl Eq for Json {}
+
+// Consistent with ==: a Number holding an integral value in the i64 range hashes like the equal
+// Integer (which also folds -0.0 into 0), every NaN hashes alike and any other Number hashes its
+// bit pattern. Object members
+// are hashed one by one and combined with a wrapping sum so the map's iteration order doesn't matter
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        type_rank(self).hash(state);
+        match self {
+            Json::Null => {},
+            Json::Boolean(b) => b.hash(state),
+            Json::Integer(i) => i.hash(state),
+            Json::Number(n) => match self.as_i64() {
+                Some(i) => i.hash(state),
+                None if n.is_nan() => f64::NAN.to_bits().hash(state),
+                None => n.to_bits().hash(state)
+            },
+            Json::String(s) => s.hash(state),
+            Json::Array(elements) => elements.hash(state),
+            Json::Object(members) => {
+                let combined = members.iter().fold(0u64, |sum, member| {
+                    let mut hasher = DefaultHasher::new();
+                    member.hash(&mut hasher);
+                    sum.wrapping_add(hasher.finish())
+                });
+                members.len().hash(state);
+                combined.hash(state);
+            }
+        }
+    }
+}
+
+// Values are ordered by type first: Null < Boolean < Number < String < Array < Object,
+// then within a type: false < true, numbers numerically, strings by code point, arrays
+// element-wise like slices and objects element-wise over their members sorted by key.
+// Integer and Number compare by value like they do for ==, so the order is total and
+// vec.sort() or binary_search work directly. NaN has a fixed place after every other number
+// and compares Equal to any other NaN
+impl Ord for Json {
+    fn cmp(&self, other: &Json) -> Ordering {
+        match (self, other) {
+            (Json::Null, Json::Null) => Ordering::Equal,
+            (Json::Boolean(a), Json::Boolean(b)) => a.cmp(b),
+            (Json::Number(a), Json::Number(b)) => a.partial_cmp(b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan())),
+            (Json::Integer(a), Json::Integer(b)) => a.cmp(b),
+            (Json::Integer(a), Json::Number(b)) => cmp_integer_number(*a, *b).unwrap_or(Ordering::Less),
+            (Json::Number(a), Json::Integer(b)) => cmp_integer_number(*b, *a).map_or(Ordering::Greater, Ordering::reverse),
+            (Json::String(a), Json::String(b)) => a.cmp(b),
+            (Json::Array(a), Json::Array(b)) => a.cmp(b),
+            (Json::Object(a), Json::Object(b)) => {
+                let mut a: Vec<(&String, &Json)> = a.iter().collect();
+                let mut b: Vec<(&String, &Json)> = b.iter().collect();
+                a.sort_unstable_by(|x, y| x.0.cmp(y.0));
+                b.sort_unstable_by(|x, y| x.0.cmp(y.0));
+                a.cmp(&b)
+            },
+            _ => type_rank(self).cmp(&type_rank(other))
+        }
+    }
+}
+
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Json) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Json {
+    /// Equality that treats an object member holding null the same as a missing member, so
+    /// `{"a": null}` equals `{}`, at any depth. Everything else follows ==, including array
+    /// elements: `[null]` still differs from `[]`, and a top-level null differs from `{}`
+    pub fn eq_ignoring_null(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Array(a), Json::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.eq_ignoring_null(y)),
+            (Json::Object(a), Json::Object(b)) => {
+                let covers = |a: &Map<String, Json>, b: &Map<String, Json>| {
+                    a.iter().all(|(key, x)| x.eq_ignoring_null(b.get(key).unwrap_or(&Json::Null)))
+                };
+                covers(a, b) && covers(b, a)
+            },
+            _ => self == other
+        }
+    }
+}
+
+impl Json {
+    /// Equality that compares arrays as multisets at any depth: `[1, 2, 3]` equals `[3, 1, 2]`,
+    /// but every element needs its own partner, so `[1, 2, 2]` differs from `[1, 1, 2]`.
+    /// Elements are matched with eq_unordered themselves, objects and scalars compare like ==.
+    /// Matching is quadratic in the array length, meant for tests and small documents
+    pub fn eq_unordered(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Array(a), Json::Array(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                let mut unmatched: Vec<&Json> = b.iter().collect();
+                a.iter().all(|x| match unmatched.iter().position(|y| x.eq_unordered(y)) {
+                    Some(i) => {
+                        unmatched.swap_remove(i);
+                        true
+                    },
+                    None => false
+                })
+            },
+            (Json::Object(a), Json::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(key, x)| b.get(key).is_some_and(|y| x.eq_unordered(y)))
+            },
+            _ => self == other
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::Json;
+
+// Implement From Trait for JSon elements
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Boolean(b)
+    }
+} 
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+} 
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+} 
+
+impl From<&String> for Json {
+    fn from(s: &String) -> Json {
+        Json::String(s.clone())
+    }
+}
+
+// A single character becomes a one-character string
+impl From<char> for Json {
+    fn from(c: char) -> Json {
+        Json::String(c.to_string())
+    }
+}
+
+// Implement Trait From for all number types through use of other macros, integers stay exact
+// as Json::Integer and only wide integer values outside the i64 range fall back to Json::Number
+macro_rules! impl_from_float_for_json {
+    ( $( $t:ident )* ) => {
+        $(
+            impl From<$t> for Json {
+                fn from(n: $t) -> Json {
+                    Json::Number(n as f64)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_int_for_json {
+    ( $( $t:ident )* ) => {
+        $(
+            impl From<$t> for Json {
+                fn from(n: $t) -> Json {
+                    Json::Integer(i64::from(n))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_wide_int_for_json {
+    ( $( $t:ident )* ) => {
+        $(
+            impl From<$t> for Json {
+                fn from(n: $t) -> Json {
+                    match i64::try_from(n) {
+                        Ok(i) => Json::Integer(i),
+                        Err(_) => Json::Number(n as f64)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_float_for_json!(f32 f64);
+impl_from_int_for_json!(u8 i8 u16 i16 u32 i32 i64);
+impl_from_wide_int_for_json!(u64 u128 i128 usize isize);
+
+// Sequences become arrays with every element converted on its own, these don't overlap with
+// the scalar impls above since no Vec or slice type is ever a scalar
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(v: Vec<T>) -> Json {
+        Json::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Clone + Into<Json>> From<&[T]> for Json {
+    fn from(v: &[T]) -> Json {
+        Json::Array(v.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Json>, const N: usize> From<[T; N]> for Json {
+    fn from(v: [T; N]) -> Json {
+        Json::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+// An existing Json value is spliced in as is through the standard library's reflexive
+// impl From<T> for T, borrowed values are cloned so the original can still be used afterwards
+impl From<&Json> for Json {
+    fn from(j: &Json) -> Json {
+        j.clone()
+    }
+}
+
+// Optional values, None becomes Json::Null
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(o: Option<T>) -> Json {
+        o.map_or(Json::Null, Into::into)
+    }
+}
+
+// Maps with string keys become objects in the same way
+impl<T: Into<Json>> From<HashMap<String, T>> for Json {
+    fn from(m: HashMap<String, T>) -> Json {
+        Json::Object(m.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<T: Into<Json>> From<BTreeMap<String, T>> for Json {
+    fn from(m: BTreeMap<String, T>) -> Json {
+        Json::Object(m.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+// Collecting values gives an array, collecting key/value pairs gives an object
+impl<T: Into<Json>> FromIterator<T> for Json {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Json {
+        Json::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Json>> FromIterator<(K, V)> for Json {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Json {
+        Json::Object(iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+// Looping over a Json yields the elements of an array, every other variant (objects included)
+// yields nothing, the same rule as iter()
+impl IntoIterator for Json {
+    type Item = Json;
+    type IntoIter = std::vec::IntoIter<Json>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Json::Array(elements) => elements.into_iter(),
+            _ => Vec::new().into_iter()
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Json {
+    type Item = &'a Json;
+    type IntoIter = std::slice::Iter<'a, Json>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Json::Array(elements) => elements.iter(),
+            _ => [].iter()
+        }
+    }
+}
+
+/// Error when a Json value is converted into a Rust type that doesn't match its variant
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected JSON {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl Json {
+    // Name of the variant as used in error messages
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Boolean(_) => "boolean",
+            Json::Number(_) | Json::Integer(_) => "number",
+            Json::String(_) => "string",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object"
+        }
+    }
+}
+
+// Typed extraction, the counterpart of the From impls above
+impl TryFrom<Json> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(j: Json) -> Result<f64, ConversionError> {
+        j.as_f64().ok_or(ConversionError { expected: "number", found: j.type_name() })
+    }
+}
+
+impl TryFrom<Json> for bool {
+    type Error = ConversionError;
+
+    fn try_from(j: Json) -> Result<bool, ConversionError> {
+        j.as_bool().ok_or(ConversionError { expected: "boolean", found: j.type_name() })
+    }
+}
+
+impl TryFrom<Json> for String {
+    type Error = ConversionError;
+
+    fn try_from(j: Json) -> Result<String, ConversionError> {
+        match j {
+            Json::String(s) => Ok(s),
+            other => Err(ConversionError { expected: "string", found: other.type_name() })
+        }
+    }
+}
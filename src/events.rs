@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::lexer::Lexer;
+use crate::{DuplicateKey, Json, ParseError, ParseErrorKind, ParseOptions};
+
+/// One step through a document as reported by Json::events, scalars arrive as a single Value
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    StartObject,
+    Key(String),
+    EndObject,
+    StartArray,
+    EndArray,
+    Value(Json)
+}
+
+// What comes next inside an open array or object
+#[derive(Clone, Copy, PartialEq)]
+enum Frame {
+    Array { first: bool },
+    Object { first: bool },
+    // A key has just been reported, its value follows
+    ObjectValue
+}
+
+/// Iterator over the events of one JSON document read from a byte stream, created by Json::events.
+/// Memory use is bounded by the read buffer, the longest string and a small entry per open container,
+/// so nesting depth isn't limited by the stack either. The first error ends the iteration
+pub struct Events<R: Read> {
+    reader: BufReader<R>,
+    line: usize,
+    column: usize,
+    offset: usize,
+    stack: Vec<Frame>,
+    // Keys seen in every open object, only kept with DuplicateKey::Error
+    keys: Vec<HashSet<String>>,
+    // Array elements and object members seen so far, checked against 'max_items'
+    items: usize,
+    // Contents of the string being read, see impl Lexer
+    string: String,
+    options: ParseOptions,
+    started: bool,
+    finished: bool
+}
+
+impl Json {
+    /// Pull events from a reader one at a time instead of building the whole tree, for documents
+    /// too large to hold in memory. The grammar is the one from_str accepts and errors point at the
+    /// same positions. A leading byte order mark is skipped and invalid UTF-8 inside a string is
+    /// InvalidUtf8 with its byte offset in the stream, like from_reader does
+    pub fn events<R: Read>(reader: R) -> Events<R> {
+        Json::events_with_options(reader, ParseOptions::default())
+    }
+
+    /// Pull events with the extensions and limits of 'options', like from_str_with_options.
+    /// Duplicate keys are only checked with DuplicateKey::Error, which member to keep is up to the consumer
+    pub fn events_with_options<R: Read>(reader: R, options: ParseOptions) -> Events<R> {
+        Events {
+            reader: BufReader::new(reader),
+            line: 1,
+            column: 1,
+            offset: 0,
+            stack: Vec::new(),
+            keys: Vec::new(),
+            items: 0,
+            string: String::new(),
+            options,
+            started: false,
+            finished: false
+        }
+    }
+}
+
+impl<R: Read> Iterator for Events<R> {
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let event = self.next_event();
+        if !matches!(event, Ok(Some(_))) {
+            self.finished = true;
+        }
+        event.transpose()
+    }
+}
+
+impl<R: Read> Events<R> {
+    // No JSON text starts with the first byte of a byte order mark, so once it is seen the rest must follow.
+    // The mark takes up bytes but no column
+    fn skip_bom(&mut self) -> Result<(), ParseError> {
+        if self.peek_byte()? != Some(0xEF) {
+            return Ok(());
+        }
+        for expected in [ 0xEF, 0xBB, 0xBF ] {
+            if self.peek_byte()? != Some(expected) {
+                return Err(self.unexpected());
+            }
+            self.reader.consume(1);
+            self.offset += 1;
+        }
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        if !self.started {
+            self.skip_bom()?;
+        }
+        self.skip_whitespace()?;
+        let Some(&frame) = self.stack.last() else {
+            if self.started {
+                return match self.peek_byte()? {
+                    None => Ok(None),
+                    Some(_) => Err(self.error(ParseErrorKind::TrailingData))
+                };
+            }
+            self.started = true;
+            return self.parse_value().map(Some);
+        };
+        let top = self.stack.len() - 1;
+        match frame {
+            Frame::ObjectValue => {
+                self.stack[top] = Frame::Object { first: false };
+                self.parse_value().map(Some)
+            },
+            Frame::Array { first } => {
+                if self.close_or_comma(b']', first)? {
+                    self.stack.pop();
+                    return Ok(Some(Event::EndArray));
+                }
+                self.stack[top] = Frame::Array { first: false };
+                self.parse_value().map(Some)
+            },
+            Frame::Object { first } => {
+                if self.close_or_comma(b'}', first)? {
+                    self.stack.pop();
+                    self.keys.pop();
+                    return Ok(Some(Event::EndObject));
+                }
+                if self.peek_byte()? != Some(b'"') {
+                    return Err(self.unexpected());
+                }
+                let key_start = self.mark();
+                let key = self.parse_string()?;
+                self.skip_whitespace()?;
+                self.expect(b':')?;
+                if let Some(keys) = self.keys.last_mut() {
+                    if !keys.insert(key.clone()) {
+                        return Err(self.error_at(key_start, ParseErrorKind::DuplicateKey(key)));
+                    }
+                }
+                self.stack[top] = Frame::ObjectValue;
+                Ok(Some(Event::Key(key)))
+            }
+        }
+    }
+
+    // True once the closing bracket is consumed, otherwise an item follows and the ',' in front
+    // of it (needed for every item but the first) has been consumed. A trailing ',' is only accepted
+    // with 'allow_trailing_commas' and every item counts towards 'max_items', both like from_str
+    fn close_or_comma(&mut self, close: u8, first: bool) -> Result<bool, ParseError> {
+        match self.peek_byte()? {
+            Some(b) if b == close => {
+                self.advance();
+                return Ok(true);
+            },
+            Some(b',') if !first => {
+                self.advance();
+                self.skip_whitespace()?;
+                if self.options.allow_trailing_commas && self.peek_byte()? == Some(close) {
+                    self.advance();
+                    return Ok(true);
+                }
+            },
+            _ if first => {},
+            _ => return Err(self.unexpected())
+        }
+        if self.items >= self.options.max_items {
+            return Err(self.error(ParseErrorKind::LimitExceeded));
+        }
+        self.items += 1;
+        Ok(false)
+    }
+
+    // Arrays and objects only take an entry on the heap, 'max_depth' still bounds how many can be
+    // open at once and the error points at the opening bracket that goes one level too deep
+    fn parse_value(&mut self) -> Result<Event, ParseError> {
+        let frame = match self.peek_byte()? {
+            Some(b'"') => return self.parse_string().map(|s| Event::Value(Json::String(s))),
+            Some(b'[') => Frame::Array { first: true },
+            Some(b'{') => Frame::Object { first: true },
+            _ => return self.parse_atom().map(Event::Value)
+        };
+        if self.stack.len() >= self.options.max_depth {
+            return Err(self.error(ParseErrorKind::DepthLimitExceeded));
+        }
+        self.advance();
+        self.stack.push(frame);
+        if frame == (Frame::Array { first: true }) {
+            return Ok(Event::StartArray);
+        }
+        if self.options.duplicate_key == DuplicateKey::Error {
+            self.keys.push(HashSet::new());
+        }
+        Ok(Event::StartObject)
+    }
+
+    // Copy one multibyte character, the bytes come straight from the reader so the sequence is
+    // checked here and an error points at its first byte
+    fn parse_utf8_char(&mut self) -> Result<usize, ParseError> {
+        let (start, offset) = (self.mark(), self.offset);
+        let mut sequence = Vec::with_capacity(4);
+        while let Some(b) = self.peek_byte()? {
+            if !sequence.is_empty() && (b & 0xC0 != 0x80 || sequence.len() == 4) {
+                break;
+            }
+            self.advance();
+            sequence.push(b);
+            if let Ok(c) = std::str::from_utf8(&sequence) {
+                self.string.push_str(c);
+                return Ok(sequence.len());
+            }
+        }
+        Err(self.error_at(start, ParseErrorKind::InvalidUtf8(offset)))
+    }
+}
+
+impl<R: Read> Lexer for Events<R> {
+    type Mark = (usize, usize);
+    type Str = String;
+
+    fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, ParseError> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into())
+            }
+        }
+    }
+
+    // Columns count characters, so UTF-8 continuation bytes don't move it
+    fn advance(&mut self) {
+        let Some(&b) = self.reader.buffer().first() else {
+            return;
+        };
+        self.reader.consume(1);
+        self.offset += 1;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if b & 0xC0 != 0x80 {
+            self.column += 1;
+        }
+    }
+
+    fn mark(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    fn error_at(&self, (line, column): (usize, usize), kind: ParseErrorKind) -> ParseError {
+        ParseError { line, column, kind }
+    }
+
+    // The character is decoded best effort since only part of it may be buffered
+    fn unexpected(&mut self) -> ParseError {
+        let c = self.reader.fill_buf().ok()
+            .and_then(|buf| String::from_utf8_lossy(&buf[..buf.len().min(4)]).chars().next());
+        match c {
+            Some(c) => self.error(ParseErrorKind::UnexpectedChar(c)),
+            None => self.error(ParseErrorKind::UnexpectedEof)
+        }
+    }
+
+    fn begin_string(&mut self) {
+        self.string.clear();
+    }
+
+    fn string_byte(&mut self) -> Result<usize, ParseError> {
+        match self.peek_byte()? {
+            Some(b @ 0x00..=0x7f) => {
+                self.advance();
+                self.string.push(char::from(b));
+                Ok(1)
+            },
+            _ => self.parse_utf8_char()
+        }
+    }
+
+    fn string_char(&mut self, _escape_start: (usize, usize), c: char) {
+        self.string.push(c);
+    }
+
+    fn finish_string(&mut self) -> String {
+        std::mem::take(&mut self.string)
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Json, ParseError, ParseErrorKind, ParseOptions};
+
+// The lexical grammar shared by the tree parser, which reads a &str in memory, and the pull parser,
+// which reads a byte stream: whitespace and comments, literals, numbers, strings and their escapes.
+// Implementors only provide byte access, error positions and the storage for string contents,
+// so both parsers accept the same tokens and report the same errors at the same positions
+pub(crate) trait Lexer {
+    // Position in the input, only turned into a line and column when an error is built
+    type Mark: Copy;
+    // Decoded contents of a string or key
+    type Str;
+
+    fn options(&self) -> &ParseOptions;
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, ParseError>;
+
+    // Consume the byte peek_byte just returned
+    fn advance(&mut self);
+
+    fn mark(&self) -> Self::Mark;
+
+    fn error_at(&self, mark: Self::Mark, kind: ParseErrorKind) -> ParseError;
+
+    // Error for whatever is at the current position when it doesn't fit the grammar
+    fn unexpected(&mut self) -> ParseError;
+
+    // String contents are handed over while parse_string reads them: begin_string after the
+    // opening quote, then string_byte for every character copied as is and string_char for every
+    // decoded escape (with the position of its backslash), and finish_string at the closing quote
+    fn begin_string(&mut self);
+
+    // Consume and store the character at the current position, returns how many bytes it had
+    fn string_byte(&mut self) -> Result<usize, ParseError>;
+
+    fn string_char(&mut self, escape_start: Self::Mark, c: char);
+
+    fn finish_string(&mut self) -> Self::Str;
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        self.error_at(self.mark(), kind)
+    }
+
+    // With 'allow_comments' // line comments and /* block comments */ count as whitespace too
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek_byte()? {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => self.advance(),
+                Some(b'/') if self.options().allow_comments => self.skip_comment()?,
+                _ => return Ok(())
+            }
+        }
+    }
+
+    // A '/' that doesn't start a comment is reported as the unexpected character, an unterminated
+    // block comment as UnexpectedEof at the end of the input
+    fn skip_comment(&mut self) -> Result<(), ParseError> {
+        let slash = self.mark();
+        self.advance();
+        match self.peek_byte()? {
+            Some(b'/') => {
+                while let Some(b) = self.peek_byte()? {
+                    if b == b'\n' {
+                        break;
+                    }
+                    self.advance();
+                }
+            },
+            Some(b'*') => {
+                self.advance();
+                let mut star = false;
+                loop {
+                    let Some(b) = self.peek_byte()? else {
+                        return Err(self.error(ParseErrorKind::UnexpectedEof));
+                    };
+                    self.advance();
+                    if star && b == b'/' {
+                        break;
+                    }
+                    star = b == b'*';
+                }
+            },
+            _ => return Err(self.error_at(slash, ParseErrorKind::UnexpectedChar('/')))
+        }
+        Ok(())
+    }
+
+    // Consume 'expected' or fail without moving past the offending character
+    fn expect(&mut self, expected: u8) -> Result<(), ParseError> {
+        if self.peek_byte()? != Some(expected) {
+            return Err(self.unexpected());
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, ParseError> {
+        for &expected in literal.as_bytes() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    // Every scalar except strings: null, true, false, numbers and with 'allow_nan' the tokens
+    // NaN, Infinity and -Infinity
+    fn parse_atom(&mut self) -> Result<Json, ParseError> {
+        let options = *self.options();
+        match self.peek_byte()? {
+            Some(b'n') => self.parse_literal("null", Json::Null),
+            Some(b't') => self.parse_literal("true", Json::Boolean(true)),
+            Some(b'f') => self.parse_literal("false", Json::Boolean(false)),
+            Some(b'N') if options.allow_nan => self.parse_literal("NaN", Json::Number(f64::NAN)),
+            Some(b'I') if options.allow_nan => self.parse_literal("Infinity", Json::Number(f64::INFINITY)),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b'+' | b'.') if options.lenient_numbers => self.parse_number(),
+            _ => Err(self.unexpected())
+        }
+    }
+
+    // Validate the strict JSON number grammar first, then let the standard library convert it,
+    // numbers without fraction or exponent that fit in an i64 become Json::Integer.
+    // The forms 'lenient_numbers' allows are all ones the standard library parses as well.
+    // Every malformed number is InvalidNumber at its first character
+    fn parse_number(&mut self) -> Result<Json, ParseError> {
+        let start = self.mark();
+        let options = *self.options();
+        let lenient = options.lenient_numbers;
+        let invalid = |lexer: &Self| lexer.error_at(start, ParseErrorKind::InvalidNumber);
+        let mut text = String::new();
+        let mut integral = true;
+        match self.peek_byte()? {
+            Some(b'-') => take_byte(self, b'-', &mut text),
+            Some(b'+') if lenient => take_byte(self, b'+', &mut text),
+            _ => {}
+        }
+        if options.allow_nan && text == "-" && self.peek_byte()? == Some(b'I') {
+            return self.parse_literal("Infinity", Json::Number(f64::NEG_INFINITY));
+        }
+        let int_start = text.len();
+        match self.peek_byte()? {
+            // Strict JSON has no leading zeros, "01" is one malformed number rather than 0 followed by 1
+            Some(b'0') if !lenient => {
+                take_byte(self, b'0', &mut text);
+                if let Some(b'0'..=b'9') = self.peek_byte()? {
+                    return Err(invalid(self));
+                }
+            },
+            Some(b'0'..=b'9') => take_digits(self, &mut text)?,
+            Some(b'.') if lenient => {},
+            _ => return Err(invalid(self))
+        }
+        let has_int_digits = text.len() > int_start;
+        if self.peek_byte()? == Some(b'.') {
+            integral = false;
+            take_byte(self, b'.', &mut text);
+            let has_fraction_digits = matches!(self.peek_byte()?, Some(b'0'..=b'9'));
+            if !(has_fraction_digits || lenient && has_int_digits) {
+                return Err(invalid(self));
+            }
+            take_digits(self, &mut text)?;
+        }
+        if let Some(b @ (b'e' | b'E')) = self.peek_byte()? {
+            integral = false;
+            take_byte(self, b, &mut text);
+            if let Some(b @ (b'+' | b'-')) = self.peek_byte()? {
+                take_byte(self, b, &mut text);
+            }
+            if !matches!(self.peek_byte()?, Some(b'0'..=b'9')) {
+                return Err(invalid(self));
+            }
+            take_digits(self, &mut text)?;
+        }
+        if integral {
+            if let Ok(i) = text.parse::<i64>() {
+                return Ok(Json::Integer(i));
+            }
+        }
+        // Rust parses an out of range literal like 1e400 as infinity, which JSON can't represent, so
+        // it is only let through where the non-finite tokens are allowed as well
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() || options.allow_nan => Ok(Json::Number(n)),
+            _ => Err(invalid(self))
+        }
+    }
+
+    // A string or key starting at its opening quote. One longer than 'max_string_length' bytes
+    // after unescaping is reported at the quote
+    fn parse_string(&mut self) -> Result<Self::Str, ParseError> {
+        let quote = self.mark();
+        self.advance();
+        self.begin_string();
+        let mut len = 0;
+        loop {
+            match self.peek_byte()? {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let escape_start = self.mark();
+                    let decoded = self.parse_escape()?;
+                    len += decoded.len_utf8();
+                    self.string_char(escape_start, decoded);
+                },
+                Some(0x00..=0x1f) => return Err(self.unexpected()),
+                Some(_) => len += self.string_byte()?,
+                None => return Err(self.error(ParseErrorKind::UnexpectedEof))
+            }
+        }
+        let s = self.finish_string();
+        self.advance();
+        if len > self.options().max_string_length {
+            return Err(self.error_at(quote, ParseErrorKind::LimitExceeded));
+        }
+        Ok(s)
+    }
+
+    // Decode one escape sequence starting at the backslash, a \uXXXX high surrogate must be
+    // followed directly by an escaped low surrogate and the pair is combined into one code point
+    fn parse_escape(&mut self) -> Result<char, ParseError> {
+        let escape_start = self.mark();
+        self.advance();
+        let Some(b) = self.peek_byte()? else {
+            return Err(self.error(ParseErrorKind::UnexpectedEof));
+        };
+        let decoded = match b {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{08}',
+            b'f' => '\u{0c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => return self.parse_unicode_escape(escape_start),
+            _ => return Err(self.error_at(escape_start, ParseErrorKind::InvalidEscape))
+        };
+        self.advance();
+        Ok(decoded)
+    }
+
+    fn parse_unicode_escape(&mut self, escape_start: Self::Mark) -> Result<char, ParseError> {
+        self.advance();
+        let invalid = |lexer: &Self| lexer.error_at(escape_start, ParseErrorKind::InvalidEscape);
+        let high = self.parse_hex4(escape_start)?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                let low_start = self.mark();
+                for expected in [ b'\\', b'u' ] {
+                    if self.peek_byte()? != Some(expected) {
+                        return Err(invalid(self));
+                    }
+                    self.advance();
+                }
+                let low = self.parse_hex4(low_start)?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(invalid(self));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            },
+            0xDC00..=0xDFFF => return Err(invalid(self)),
+            _ => high
+        };
+        char::from_u32(code).ok_or_else(|| invalid(self))
+    }
+
+    // Exactly four hex digits, errors are reported at the start of the escape they belong to
+    fn parse_hex4(&mut self, escape_start: Self::Mark) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let Some(digit) = self.peek_byte()?.and_then(|b| char::from(b).to_digit(16)) else {
+                return Err(self.error_at(escape_start, ParseErrorKind::InvalidEscape));
+            };
+            code = code * 16 + digit;
+            self.advance();
+        }
+        Ok(code)
+    }
+}
+
+// Consume the byte peek_byte returned into the text of a number, those characters are all ASCII
+fn take_byte<L: Lexer + ?Sized>(lexer: &mut L, b: u8, text: &mut String) {
+    text.push(char::from(b));
+    lexer.advance();
+}
+
+fn take_digits<L: Lexer + ?Sized>(lexer: &mut L, text: &mut String) -> Result<(), ParseError> {
+    while let Some(b @ b'0'..=b'9') = lexer.peek_byte()? {
+        take_byte(lexer, b, text);
+    }
+    Ok(())
+}
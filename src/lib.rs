@@ -0,0 +1,104 @@
+//! A small JSON library: a `Json` value type, the `json!` macro for building values from
+//! JSON-like Rust syntax, a parser for JSON text and compact or pretty serialization.
+//!
+//! ```
+//! use json_moro::{json, Json};
+//!
+//! let width = 100;
+//! let value = json!( { "width" : width, "elements" : [ 1, 2, 3 ], "dummy" : { "overview" : true } } );
+//! assert_eq!(value["dummy"]["overview"], Json::Boolean(true));
+//! assert_eq!(Json::from_str(&value.to_string()), Ok(value));
+//! ```
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_support;
+mod cmp;
+mod convert;
+mod events;
+mod lexer;
+mod parser;
+mod patch;
+mod pointer;
+mod query;
+mod schema;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "serde_json")]
+mod serde_json_support;
+mod serializer;
+mod value;
+
+pub use convert::ConversionError;
+pub use events::{Event, Events};
+pub use parser::{DuplicateKey, JsonBorrowed, LoadError, ParseError, ParseErrorKind, ParseOptions};
+pub use patch::{ArrayMerge, Change, MergeOptions, PatchError};
+pub use query::QueryError;
+pub use schema::ValidationError;
+pub use serializer::{NonFiniteError, SerializeOptions};
+pub use value::{Entry, JsonBuilder, JsonStats};
+
+/// Map type for object members, with the 'preserve_order' feature members keep their insertion order
+#[cfg(not(feature = "preserve_order"))]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+/// Map type for object members, with the 'preserve_order' feature members keep their insertion order
+#[cfg(feature = "preserve_order")]
+pub type Map<K, V> = indexmap::IndexMap<K, V>;
+
+// Remove an object member, with 'preserve_order' the remaining members keep their order
+pub(crate) fn remove_member(members: &mut Map<String, Json>, key: &str) -> Option<Json> {
+    #[cfg(not(feature = "preserve_order"))]
+    return members.remove(key);
+    #[cfg(feature = "preserve_order")]
+    return members.shift_remove(key);
+}
+
+/// JSON Data types, Debug lists object members sorted by key so its output is reproducible
+#[derive(Clone, Default)]
+pub enum Json {
+    #[default]
+    Null,
+    Boolean(bool),
+    /// Any number with a fraction or exponent, or an integer outside the i64 range
+    Number(f64),
+    /// Integral numbers that fit in an i64 are kept exact
+    Integer(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Map<String, Json>)
+}
+
+/// JSON parser macro, object keys are either literals or a parenthesized expression like
+/// json!({ (name) : 1 }) that is evaluated at runtime and turned into a String with to_string()
+// All paths are fully qualified so the macro works without Json or anything else imported.
+// Arrays and objects are allocated once at their final size: vec! with a list of elements
+// already does that and objects get a map with capacity for one member per key
+#[macro_export]
+macro_rules! json {
+    // Internal rules counting the keys of an object literal at compile time
+    (@count $( $key:tt )*) => {
+        <[()]>::len(&[ $( $crate::json!(@unit $key) ), * ])
+    };
+    (@unit $key:tt) => {
+        ()
+    };
+    ( null ) => {
+        $crate::Json::Null
+    };
+    ([ $( $element:tt ), * $(,)? ]) => {
+        $crate::Json::Array(::std::vec! [ $( $crate::json!($element) ), * ])
+    };
+    ( { $( $key:tt : $value:tt ), * $(,)? } ) => {{
+        #[allow(unused_mut)]
+        let mut members = $crate::Map::with_capacity($crate::json!(@count $( $key )*));
+        $( members.insert(::std::string::ToString::to_string(&$key), $crate::json!( $value )); )*
+        $crate::Json::Object(members)
+    }};
+    ( $other:tt ) => {
+        <$crate::Json as ::std::convert::From<_>>::from( $other )
+    };
+}
+
+// Unittests for json parser macro
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants, clippy::unnecessary_cast, clippy::collapsible_match)]
+mod tests;
@@ -0,0 +1,608 @@
+use std::borrow::{Borrow, Cow};
+use std::fmt;
+use std::hash::Hash;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::lexer::Lexer;
+use crate::{Json, Map};
+
+/// The different reasons the runtime parser can reject its input
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEof,
+    InvalidNumber,
+    InvalidEscape,
+    TrailingData,
+    DepthLimitExceeded,
+    DuplicateKey(String),
+    LimitExceeded,
+    /// Byte offset of the first byte that isn't part of a well-formed UTF-8 sequence
+    InvalidUtf8(usize),
+    Io(io::ErrorKind)
+}
+
+/// Error from the runtime parser, 'line' and 'column' are 1-based and point at the offending character,
+/// both are 0 for I/O errors since those don't belong to a position in the input
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ParseErrorKind::UnexpectedEof => f.write_str("unexpected end of input"),
+            ParseErrorKind::InvalidNumber => f.write_str("invalid number"),
+            ParseErrorKind::InvalidEscape => f.write_str("invalid escape sequence"),
+            ParseErrorKind::TrailingData => f.write_str("trailing characters after JSON value"),
+            ParseErrorKind::DepthLimitExceeded => f.write_str("nesting depth limit exceeded"),
+            ParseErrorKind::DuplicateKey(key) => write!(f, "duplicate object key \"{}\"", key),
+            ParseErrorKind::LimitExceeded => f.write_str("size limit exceeded"),
+            ParseErrorKind::InvalidUtf8(offset) => write!(f, "invalid UTF-8 sequence at byte {}", offset),
+            ParseErrorKind::Io(kind) => write!(f, "I/O error: {}", kind)
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::Io(_) => write!(f, "{}", self.kind),
+            _ => write!(f, "{} at line {}, column {}", self.kind, self.line, self.column)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> ParseError {
+        ParseError { line: 0, column: 0, kind: ParseErrorKind::Io(e.kind()) }
+    }
+}
+
+/// Error from Json::from_file, either the file couldn't be read or its contents aren't valid JSON
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(ParseError)
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "cannot read JSON file: {}", e),
+            LoadError::Parse(e) => write!(f, "invalid JSON file: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse(e) => Some(e)
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(e: ParseError) -> LoadError {
+        LoadError::Parse(e)
+    }
+}
+
+/// What the parser does when an object contains the same key more than once
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum DuplicateKey {
+    UseFirst,
+    #[default]
+    UseLast,
+    Error
+}
+
+/// Settings for from_str_with_options, the defaults are what from_str uses: nesting up to
+/// DEFAULT_MAX_DEPTH but no size limits, the last of several values for the same key wins and
+/// neither comments nor trailing commas
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ParseOptions {
+    /// Most arrays and objects nested inside each other. The parser recurses once per level,
+    /// so this is what keeps a document like "[[[[..." from overflowing the stack
+    pub max_depth: usize,
+    pub duplicate_key: DuplicateKey,
+    pub allow_comments: bool,
+    pub allow_trailing_commas: bool,
+    /// Accept the bare tokens NaN, Infinity and -Infinity as non-finite numbers, Display writes
+    /// those back as null, use try_to_string to catch them before serializing
+    pub allow_nan: bool,
+    /// Accept a leading '+', leading zeros ("007"), a missing integer part (".5") and a missing
+    /// fraction after the point ("1."), every number still needs at least one digit
+    pub lenient_numbers: bool,
+    /// Total number of array elements and object members in the whole document
+    pub max_items: usize,
+    /// Longest string or key allowed, in bytes after unescaping
+    pub max_string_length: usize
+}
+
+impl ParseOptions {
+    /// Nesting limit of the default options, deep enough for real documents and far below what
+    /// exhausts the stack of a thread in an unoptimized build
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: ParseOptions::DEFAULT_MAX_DEPTH,
+            duplicate_key: DuplicateKey::default(),
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_nan: false,
+            lenient_numbers: false,
+            max_items: usize::MAX,
+            max_string_length: usize::MAX
+        }
+    }
+}
+
+// The value types the parser can build, it only decides how strings and containers are stored.
+// Scalars come in as the matching Json variant (Null, Boolean, Number or Integer)
+trait ParsedValue<'a>: Sized {
+    type Key: Hash + Eq + Borrow<str>;
+
+    fn from_scalar(value: Json) -> Self;
+    fn from_string(s: Cow<'a, str>) -> Self;
+    fn from_key(s: Cow<'a, str>) -> Self::Key;
+    fn from_array(elements: Vec<Self>) -> Self;
+    fn from_object(members: Map<Self::Key, Self>) -> Self;
+}
+
+impl<'a> ParsedValue<'a> for Json {
+    type Key = String;
+
+    fn from_scalar(value: Json) -> Self {
+        value
+    }
+
+    fn from_string(s: Cow<'a, str>) -> Self {
+        Json::String(s.into_owned())
+    }
+
+    fn from_key(s: Cow<'a, str>) -> String {
+        s.into_owned()
+    }
+
+    fn from_array(elements: Vec<Self>) -> Self {
+        Json::Array(elements)
+    }
+
+    fn from_object(members: Map<String, Json>) -> Self {
+        Json::Object(members)
+    }
+}
+
+// Recursive descent parser working directly on the bytes of the input text
+struct Parser<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    // Number of arrays and objects currently open
+    depth: usize,
+    // Array elements and object members seen so far, checked against 'max_items'
+    items: usize,
+    options: ParseOptions,
+    // Start of the string text not yet copied and the decoded string so far, see impl Lexer
+    segment: usize,
+    owned: Option<String>
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, options: ParseOptions) -> Self {
+        Parser { input, bytes: input.as_bytes(), pos: 0, depth: 0, items: 0, options, segment: 0, owned: None }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    // parse_value, parse_nested, parse_array and parse_object recurse for every nesting level,
+    // so they only hold what has to live across the recursive call and leave everything else
+    // to helpers, that keeps unoptimized builds from running out of stack on deep documents
+    fn parse_value<V: ParsedValue<'a>>(&mut self) -> Result<V, ParseError> {
+        match self.peek() {
+            Some(b'[' | b'{') => self.parse_nested(),
+            _ => self.parse_scalar()
+        }
+    }
+
+    fn parse_scalar<V: ParsedValue<'a>>(&mut self) -> Result<V, ParseError> {
+        match self.peek() {
+            Some(b'"') => self.parse_string().map(V::from_string),
+            _ => self.parse_atom().map(V::from_scalar)
+        }
+    }
+
+    // Arrays and objects recurse, so the nesting is bounded before the stack is,
+    // the error points at the opening bracket that goes one level too deep
+    fn parse_nested<V: ParsedValue<'a>>(&mut self) -> Result<V, ParseError> {
+        if self.depth >= self.options.max_depth {
+            return Err(self.error(ParseErrorKind::DepthLimitExceeded));
+        }
+        self.depth += 1;
+        let value = match self.peek() {
+            Some(b'[') => self.parse_array(),
+            _ => self.parse_object()
+        };
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_array<V: ParsedValue<'a>>(&mut self) -> Result<V, ParseError> {
+        self.pos += 1;
+        let mut elements = Vec::new();
+        while self.next_item(b']', elements.is_empty())? {
+            elements.push(self.parse_value()?);
+        }
+        Ok(V::from_array(elements))
+    }
+
+    fn parse_object<V: ParsedValue<'a>>(&mut self) -> Result<V, ParseError> {
+        self.pos += 1;
+        let mut members = Map::new();
+        while self.next_item(b'}', members.is_empty())? {
+            let (key_start, key) = self.parse_key()?;
+            let value = self.parse_value()?;
+            self.insert_member(&mut members, key_start, key, value)?;
+        }
+        Ok(V::from_object(members))
+    }
+
+    // Move to the next array element or object member, false once the closing bracket is consumed.
+    // Items after the first need a ',' in front of them, a trailing one is only accepted with
+    // 'allow_trailing_commas'. Every item counts towards 'max_items', the error points at the
+    // first item over the limit
+    fn next_item(&mut self, close: u8, first: bool) -> Result<bool, ParseError> {
+        self.skip_whitespace()?;
+        if !first {
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace()?;
+                    if !(self.options.allow_trailing_commas && self.peek() == Some(close)) {
+                        return self.count_item();
+                    }
+                },
+                Some(b) if b == close => {},
+                _ => return Err(self.unexpected())
+            }
+        }
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(false);
+        }
+        self.count_item()
+    }
+
+    fn count_item(&mut self) -> Result<bool, ParseError> {
+        if self.items >= self.options.max_items {
+            return Err(self.error(ParseErrorKind::LimitExceeded));
+        }
+        self.items += 1;
+        Ok(true)
+    }
+
+    // A member key and the ':' after it, together with where the key starts for error reporting
+    fn parse_key(&mut self) -> Result<(usize, Cow<'a, str>), ParseError> {
+        if self.peek() != Some(b'"') {
+            return Err(self.unexpected());
+        }
+        let key_start = self.pos;
+        let key = self.parse_string()?;
+        self.skip_whitespace()?;
+        self.expect(b':')?;
+        self.skip_whitespace()?;
+        Ok((key_start, key))
+    }
+
+    fn insert_member<V: ParsedValue<'a>>(&self, members: &mut Map<V::Key, V>, key_start: usize, key: Cow<'a, str>, value: V) -> Result<(), ParseError> {
+        match self.options.duplicate_key {
+            DuplicateKey::UseLast => { members.insert(V::from_key(key), value); },
+            DuplicateKey::UseFirst => { members.entry(V::from_key(key)).or_insert(value); },
+            DuplicateKey::Error if members.contains_key(key.as_ref()) => {
+                return Err(self.error_at(key_start, ParseErrorKind::DuplicateKey(key.into_owned())));
+            },
+            DuplicateKey::Error => { members.insert(V::from_key(key), value); }
+        }
+        Ok(())
+    }
+}
+
+// Strings without escapes are borrowed from the input, the first escape switches to an owned copy
+// that collects the text between escapes from 'segment' on
+impl<'a> Lexer for Parser<'a> {
+    type Mark = usize;
+    type Str = Cow<'a, str>;
+
+    fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, ParseError> {
+        Ok(self.peek())
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+
+    fn mark(&self) -> usize {
+        self.pos
+    }
+
+    // Line and column are only needed when something goes wrong, so they are computed from the byte offset
+    fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        ParseError { line, column, kind }
+    }
+
+    fn unexpected(&mut self) -> ParseError {
+        match self.input[self.pos..].chars().next() {
+            Some(c) => self.error(ParseErrorKind::UnexpectedChar(c)),
+            None => self.error(ParseErrorKind::UnexpectedEof)
+        }
+    }
+
+    fn begin_string(&mut self) {
+        self.segment = self.pos;
+        self.owned = None;
+    }
+
+    // The input is a &str, so the bytes of a multibyte character can be taken one at a time
+    fn string_byte(&mut self) -> Result<usize, ParseError> {
+        self.pos += 1;
+        Ok(1)
+    }
+
+    fn string_char(&mut self, escape_start: usize, c: char) {
+        let out = self.owned.get_or_insert_with(String::new);
+        out.push_str(&self.input[self.segment..escape_start]);
+        out.push(c);
+        self.segment = self.pos;
+    }
+
+    fn finish_string(&mut self) -> Cow<'a, str> {
+        let tail = &self.input[self.segment..self.pos];
+        match self.owned.take() {
+            None => Cow::Borrowed(tail),
+            Some(mut out) => {
+                out.push_str(tail);
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
+// Strict JSON has no byte order mark but plenty of files start with one, positions are counted after it
+fn strip_bom(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input)
+}
+
+// The prefix up to the first bad byte is valid UTF-8, so line and column can be counted in it
+fn invalid_utf8(bytes: &[u8], error: std::str::Utf8Error) -> ParseError {
+    let offset = error.valid_up_to();
+    let valid = strip_bom(std::str::from_utf8(&bytes[..offset]).unwrap_or_default());
+    Parser::new(valid, ParseOptions::default()).error_at(valid.len(), ParseErrorKind::InvalidUtf8(offset))
+}
+
+// The whole input must be exactly one JSON value with optional whitespace around it
+fn parse_document<'a, V: ParsedValue<'a>>(input: &'a str, options: ParseOptions) -> Result<V, ParseError> {
+    let mut parser = Parser::new(strip_bom(input), options);
+    parser.skip_whitespace()?;
+    let value = parser.parse_value()?;
+    parser.skip_whitespace()?;
+    if parser.peek().is_some() {
+        return Err(parser.error(ParseErrorKind::TrailingData));
+    }
+    Ok(value)
+}
+
+impl Json {
+    /// Parse JSON text into a value, the whole input must be exactly one JSON value.
+    /// When a key occurs more than once in an object the last value wins, like most JSON parsers.
+    /// A leading byte order mark is skipped, as it is by the other functions that parse exactly one document
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &str) -> Result<Json, ParseError> {
+        Json::from_str_with_options(input, ParseOptions::default())
+    }
+
+    /// Like from_str with another limit for the number of arrays and objects nested inside each other,
+    /// for documents deeper than ParseOptions::DEFAULT_MAX_DEPTH or to be stricter with untrusted input
+    pub fn from_str_with_limit(input: &str, max_depth: usize) -> Result<Json, ParseError> {
+        Json::from_str_with_options(input, ParseOptions { max_depth, ..ParseOptions::default() })
+    }
+
+    /// Parse with explicit settings, with DuplicateKey::Error a repeated key is reported at its second occurrence
+    pub fn from_str_with_options(input: &str, options: ParseOptions) -> Result<Json, ParseError> {
+        parse_document(input, options)
+    }
+
+    /// Parse the first value of 'input', leading whitespace is skipped and anything after the
+    /// value is ignored. Returns the value and the byte offset just past it, so a concatenation
+    /// like "true false 42" or "[1][2]" can be read by parsing again from that offset. Error
+    /// positions are relative to 'input', input with only whitespace left is UnexpectedEof
+    pub fn from_str_partial(input: &str) -> Result<(Json, usize), ParseError> {
+        let mut parser = Parser::new(input, ParseOptions::default());
+        parser.skip_whitespace()?;
+        let value = parser.parse_value()?;
+        Ok((value, parser.pos))
+    }
+
+    /// Parse every value of a concatenation like "1 2 [3] {}", values may be separated by
+    /// whitespace or directly follow each other as in "[1][2]". Unlike from_str_partial the whole
+    /// input has to be consumed, an empty input gives no values and error positions are in 'input'
+    pub fn parse_multiple(input: &str) -> Result<Vec<Json>, ParseError> {
+        let mut parser = Parser::new(input, ParseOptions::default());
+        let mut values = Vec::new();
+        parser.skip_whitespace()?;
+        while parser.peek().is_some() {
+            values.push(parser.parse_value()?);
+            parser.skip_whitespace()?;
+        }
+        Ok(values)
+    }
+
+    /// Tolerant parsing for hand written config files, accepts // and /* */ comments wherever
+    /// whitespace may appear and a trailing comma before ] or }, the rest of the grammar stays strict
+    pub fn from_json5(input: &str) -> Result<Json, ParseError> {
+        Json::from_str_with_options(input, ParseOptions { allow_comments: true, allow_trailing_commas: true, ..ParseOptions::default() })
+    }
+
+    /// Parse without copying strings that contain no escapes, they borrow from 'input' instead.
+    /// Same grammar and defaults as from_str, JsonBorrowed::into_owned gives a regular Json
+    pub fn from_str_borrowed(input: &str) -> Result<JsonBorrowed<'_>, ParseError> {
+        parse_document(input, ParseOptions::default())
+    }
+
+    /// Parse JSON from raw bytes, input that isn't valid UTF-8 gives ParseErrorKind::InvalidUtf8
+    /// with the byte offset of the first bad sequence and its line and column. A leading UTF-8
+    /// byte order mark is skipped like from_str does
+    pub fn from_slice(bytes: &[u8]) -> Result<Json, ParseError> {
+        match std::str::from_utf8(bytes) {
+            Ok(input) => Json::from_str(input),
+            Err(e) => Err(invalid_utf8(bytes, e))
+        }
+    }
+
+    /// Parse JSON text from a reader, the input is buffered internally so callers don't need to
+    /// collect it themselves. Read failures give ParseErrorKind::Io, the bytes are checked like from_slice does
+    pub fn from_reader<R: io::Read>(mut r: R) -> Result<Json, ParseError> {
+        let mut input = Vec::new();
+        r.read_to_end(&mut input)?;
+        Json::from_slice(&input)
+    }
+
+    /// Read and parse a JSON file. The bytes are checked like from_slice does, but
+    /// failures to open or read it keep the full io::Error in LoadError::Io
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Json, LoadError> {
+        let mut input = Vec::new();
+        File::open(path)?.read_to_end(&mut input)?;
+        Ok(Json::from_slice(&input)?)
+    }
+
+    /// Parse newline-delimited JSON, one value per line and blank lines are skipped,
+    /// a failing record is reported with its line number in the whole input
+    pub fn from_ndjson(input: &str) -> Result<Vec<Json>, ParseError> {
+        let mut values = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = Json::from_str(line).map_err(|e| ParseError { line: i + 1, ..e })?;
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+/// Same as Json::from_str, so generic code and `str::parse` work too
+///
+/// ```
+/// use json_moro::{json, Json, ParseError};
+///
+/// fn width(input: &str) -> Result<Json, ParseError> {
+///     let value: Json = input.parse()?;
+///     Ok(value["width"].clone())
+/// }
+///
+/// assert_eq!(width(r#"{ "width" : 100 }"#), Ok(json!( 100 )));
+/// assert!(width("{ width : 100 }").is_err());
+/// ```
+impl std::str::FromStr for Json {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Json, ParseError> {
+        Json::from_str(input)
+    }
+}
+
+/// A parsed value that borrows strings and keys from the input text where it can, otherwise
+/// identical to Json. Only strings containing escapes had to be decoded and are owned
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonBorrowed<'a> {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    Integer(i64),
+    String(Cow<'a, str>),
+    Array(Vec<JsonBorrowed<'a>>),
+    Object(Map<Cow<'a, str>, JsonBorrowed<'a>>)
+}
+
+impl<'a> ParsedValue<'a> for JsonBorrowed<'a> {
+    type Key = Cow<'a, str>;
+
+    fn from_scalar(value: Json) -> Self {
+        JsonBorrowed::from(value)
+    }
+
+    fn from_string(s: Cow<'a, str>) -> Self {
+        JsonBorrowed::String(s)
+    }
+
+    fn from_key(s: Cow<'a, str>) -> Cow<'a, str> {
+        s
+    }
+
+    fn from_array(elements: Vec<Self>) -> Self {
+        JsonBorrowed::Array(elements)
+    }
+
+    fn from_object(members: Map<Cow<'a, str>, Self>) -> Self {
+        JsonBorrowed::Object(members)
+    }
+}
+
+impl JsonBorrowed<'_> {
+    /// Copy every borrowed string so the result no longer depends on the input text
+    pub fn into_owned(self) -> Json {
+        match self {
+            JsonBorrowed::Null => Json::Null,
+            JsonBorrowed::Boolean(b) => Json::Boolean(b),
+            JsonBorrowed::Number(n) => Json::Number(n),
+            JsonBorrowed::Integer(i) => Json::Integer(i),
+            JsonBorrowed::String(s) => Json::String(s.into_owned()),
+            JsonBorrowed::Array(elements) => Json::Array(elements.into_iter().map(JsonBorrowed::into_owned).collect()),
+            JsonBorrowed::Object(members) => Json::Object(members.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect())
+        }
+    }
+}
+
+// An owned Json fits as is, every string simply ends up as Cow::Owned
+impl From<Json> for JsonBorrowed<'_> {
+    fn from(value: Json) -> Self {
+        match value {
+            Json::Null => JsonBorrowed::Null,
+            Json::Boolean(b) => JsonBorrowed::Boolean(b),
+            Json::Number(n) => JsonBorrowed::Number(n),
+            Json::Integer(i) => JsonBorrowed::Integer(i),
+            Json::String(s) => JsonBorrowed::String(Cow::Owned(s)),
+            Json::Array(elements) => JsonBorrowed::Array(elements.into_iter().map(JsonBorrowed::from).collect()),
+            Json::Object(members) => JsonBorrowed::Object(members.into_iter().map(|(k, v)| (Cow::Owned(k), JsonBorrowed::from(v))).collect())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use crate::pointer::{pointer_index, pointer_push, pointer_tokens};
+use crate::{remove_member, Json, Map};
+
+/// How merge_with_options combines two arrays found at the same place
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ArrayMerge {
+    /// The array from 'other' replaces the existing one, like any other non-object value
+    #[default]
+    Replace,
+    /// Elements from 'other' are appended after the existing ones
+    Concat,
+    /// Elements at the same index are merged recursively, when the lengths differ the extra
+    /// elements of the longer array are kept as they are, so the result is never shorter
+    ByIndex
+}
+
+/// Settings for merge_with_options, the defaults are what merge uses
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MergeOptions {
+    pub arrays: ArrayMerge
+}
+
+impl Json {
+    /// Deep merge 'other' into self, 'other' always wins on conflicts: keys only in 'other' are
+    /// inserted, keys where both sides hold objects are merged recursively and any other value
+    /// from 'other' overwrites the existing one. When either side isn't an object 'other' replaces self
+    pub fn merge(&mut self, other: Json) {
+        self.merge_with_options(other, MergeOptions::default());
+    }
+
+    /// Like merge, with 'options' deciding what happens when both sides hold arrays
+    pub fn merge_with_options(&mut self, other: Json, options: MergeOptions) {
+        match (self, other) {
+            (Json::Object(base), Json::Object(overlay)) => {
+                for (key, value) in overlay {
+                    match base.get_mut(&key) {
+                        Some(existing) => existing.merge_with_options(value, options),
+                        None => {
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            },
+            (Json::Array(base), Json::Array(overlay)) if options.arrays != ArrayMerge::Replace => {
+                let mut overlay = overlay.into_iter();
+                if options.arrays == ArrayMerge::ByIndex {
+                    for (existing, value) in base.iter_mut().zip(overlay.by_ref()) {
+                        existing.merge_with_options(value, options);
+                    }
+                }
+                base.extend(overlay);
+            },
+            (target, other) => *target = other
+        }
+    }
+
+    /// RFC 7386 JSON Merge Patch, unlike merge a null member in the patch removes that key
+    /// from the target and a patch object applied to a non-object starts from an empty object
+    pub fn apply_merge_patch(&mut self, patch: &Json) {
+        let Json::Object(patch_members) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !self.is_object() {
+            *self = Json::Object(Map::default());
+        }
+        if let Json::Object(members) = self {
+            for (key, value) in patch_members.iter() {
+                if value.is_null() {
+                    remove_member(members, key);
+                } else {
+                    members.entry(key.clone()).or_insert(Json::Null).apply_merge_patch(value);
+                }
+            }
+        }
+    }
+}
+
+/// One difference reported by Json::diff, 'path' is the JSON Pointer of the member or element
+/// that differs, in the old document for Removed and Changed and in the new one for Added
+#[derive(Clone, PartialEq, Debug)]
+pub enum Change {
+    Added { path: String, value: Json },
+    Removed { path: String, value: Json },
+    Changed { path: String, old: Json, new: Json }
+}
+
+impl Json {
+    /// Structural difference from 'old' to 'new'. Objects are compared member by member in key order
+    /// and arrays element by element by index, so a longer or shorter array gives Added or Removed
+    /// for the tail. Where both sides are objects or both arrays the comparison recurses, any other
+    /// values that aren't == give one Changed for the whole value. Equal documents give no changes
+    pub fn diff(old: &Json, new: &Json) -> Vec<Change> {
+        let mut changes = Vec::new();
+        diff_into(old, new, "", &mut changes);
+        changes
+    }
+}
+
+fn diff_into(old: &Json, new: &Json, path: &str, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Json::Object(old_members), Json::Object(new_members)) => {
+            let mut keys: Vec<&String> = old_members.keys().chain(new_members.keys().filter(|k| !old_members.contains_key(*k))).collect();
+            keys.sort_unstable();
+            for key in keys {
+                let path = pointer_push(path, key);
+                match (old_members.get(key), new_members.get(key)) {
+                    (Some(old), Some(new)) => diff_into(old, new, &path, changes),
+                    (Some(old), None) => changes.push(Change::Removed { path, value: old.clone() }),
+                    (None, Some(new)) => changes.push(Change::Added { path, value: new.clone() }),
+                    (None, None) => {}
+                }
+            }
+        },
+        (Json::Array(old_elements), Json::Array(new_elements)) => {
+            for i in 0..old_elements.len().max(new_elements.len()) {
+                let path = format!("{}/{}", path, i);
+                match (old_elements.get(i), new_elements.get(i)) {
+                    (Some(old), Some(new)) => diff_into(old, new, &path, changes),
+                    (Some(old), None) => changes.push(Change::Removed { path, value: old.clone() }),
+                    (None, Some(new)) => changes.push(Change::Added { path, value: new.clone() }),
+                    (None, None) => {}
+                }
+            }
+        },
+        _ if old != new => changes.push(Change::Changed { path: path.to_string(), old: old.clone(), new: new.clone() }),
+        _ => {}
+    }
+}
+
+/// Reasons an RFC 6902 JSON Patch can't be applied
+#[derive(Clone, PartialEq, Debug)]
+pub enum PatchError {
+    /// The patch isn't an array of well-formed operation objects, the message says what is wrong
+    InvalidPatch(String),
+    /// A 'path' or 'from' pointer doesn't resolve to a location the operation can use
+    PathNotFound(String),
+    /// A "test" operation found a different value at this path
+    TestFailed(String)
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidPatch(reason) => write!(f, "invalid JSON patch: {}", reason),
+            PatchError::PathNotFound(path) => write!(f, "JSON patch path \"{}\" does not exist", path),
+            PatchError::TestFailed(path) => write!(f, "JSON patch test failed at \"{}\"", path)
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+// Split a pointer into the pointer of its parent and the unescaped last token, None for the root
+fn pointer_parent(path: &str) -> Result<Option<(&str, String)>, PatchError> {
+    let tokens = pointer_tokens(path).ok_or_else(|| PatchError::InvalidPatch(format!("\"{}\" is not a JSON pointer", path)))?;
+    Ok(tokens.last().map(|last| (&path[..path.rfind('/').unwrap_or(0)], last.clone())))
+}
+
+// String member of an operation object
+fn patch_member<'a>(op: &'a Json, index: usize, name: &str) -> Result<&'a str, PatchError> {
+    op.get(name)
+        .and_then(Json::as_str)
+        .ok_or_else(|| PatchError::InvalidPatch(format!("operation {} has no string \"{}\"", index, name)))
+}
+
+impl Json {
+    /// Apply an RFC 6902 JSON Patch, an array of operation objects with "op" being one of add,
+    /// remove, replace, move, copy or test and "path" / "from" JSON Pointers. The operations run
+    /// in order on a copy that only replaces self once all of them succeeded, so on error self is unchanged
+    pub fn apply_patch(&mut self, patch: &Json) -> Result<(), PatchError> {
+        let operations = patch.as_array().ok_or_else(|| PatchError::InvalidPatch(String::from("patch is not an array")))?;
+        let mut doc = self.clone();
+        for (index, op) in operations.iter().enumerate() {
+            let path = patch_member(op, index, "path")?;
+            let value = || op.get("value").cloned().ok_or_else(|| PatchError::InvalidPatch(format!("operation {} has no \"value\"", index)));
+            match patch_member(op, index, "op")? {
+                "add" => doc.patch_add(path, value()?)?,
+                "remove" => {
+                    doc.patch_remove(path)?;
+                },
+                "replace" => {
+                    // Checked with pointer first since pointer_mut appends for a trailing "-"
+                    if doc.pointer(path).is_none() {
+                        return Err(PatchError::PathNotFound(path.to_string()));
+                    }
+                    if let Some(target) = doc.pointer_mut(path) {
+                        *target = value()?;
+                    }
+                },
+                "move" => {
+                    let from = patch_member(op, index, "from")?;
+                    if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                        return Err(PatchError::InvalidPatch(format!("operation {} moves \"{}\" into itself", index, from)));
+                    }
+                    let moved = doc.patch_remove(from)?;
+                    doc.patch_add(path, moved)?;
+                },
+                "copy" => {
+                    let from = patch_member(op, index, "from")?;
+                    let copied = doc.pointer(from).cloned().ok_or_else(|| PatchError::PathNotFound(from.to_string()))?;
+                    doc.patch_add(path, copied)?;
+                },
+                "test" => {
+                    if doc.pointer(path) != Some(&value()?) {
+                        return Err(PatchError::TestFailed(path.to_string()));
+                    }
+                },
+                other => return Err(PatchError::InvalidPatch(format!("operation {} has unknown op \"{}\"", index, other)))
+            }
+        }
+        *self = doc;
+        Ok(())
+    }
+
+    // The "add" operation: replaces the root, sets an object member or inserts into an array
+    // at an index up to its length, "-" appends
+    fn patch_add(&mut self, path: &str, value: Json) -> Result<(), PatchError> {
+        let Some((parent, token)) = pointer_parent(path)? else {
+            *self = value;
+            return Ok(());
+        };
+        match self.pointer_mut(parent) {
+            Some(Json::Object(members)) => {
+                members.insert(token, value);
+            },
+            Some(Json::Array(elements)) => {
+                let index = if token == "-" { Some(elements.len()) } else { pointer_index(&token) };
+                match index {
+                    Some(i) if i <= elements.len() => elements.insert(i, value),
+                    _ => return Err(PatchError::PathNotFound(path.to_string()))
+                }
+            },
+            _ => return Err(PatchError::PathNotFound(path.to_string()))
+        }
+        Ok(())
+    }
+
+    // The "remove" operation, the value at 'path' must exist and is handed back for "move"
+    fn patch_remove(&mut self, path: &str) -> Result<Json, PatchError> {
+        let not_found = || PatchError::PathNotFound(path.to_string());
+        let Some((parent, token)) = pointer_parent(path)? else {
+            return Err(PatchError::InvalidPatch(String::from("the whole document can't be removed")));
+        };
+        match self.pointer_mut(parent) {
+            Some(Json::Object(members)) => remove_member(members, &token).ok_or_else(not_found),
+            Some(Json::Array(elements)) => match pointer_index(&token) {
+                Some(i) if i < elements.len() => Ok(elements.remove(i)),
+                _ => Err(not_found())
+            },
+            _ => Err(not_found())
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::{Json, Map};
+
+// Split an RFC 6901 JSON Pointer into unescaped reference tokens, None if it isn't a valid pointer
+pub(crate) fn pointer_tokens(ptr: &str) -> Option<Vec<String>> {
+    if ptr.is_empty() {
+        return Some(Vec::new());
+    }
+    let tokens = ptr.strip_prefix('/')?.split('/');
+    Some(tokens.map(|token| token.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+// Append one escaped reference token to a JSON Pointer, the inverse of pointer_tokens
+pub(crate) fn pointer_push(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+// Array index tokens are plain decimal numbers without leading zeros
+pub(crate) fn pointer_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl Json {
+    /// Look up a value by JSON Pointer like "/elements/2", the empty pointer is the whole document.
+    /// The RFC 6901 token "-" names the element after the last one of an array, which doesn't exist yet, so it gives None
+    pub fn pointer(&self, ptr: &str) -> Option<&Json> {
+        let mut target = self;
+        for token in pointer_tokens(ptr)? {
+            target = match target {
+                Json::Object(members) => members.get(&token)?,
+                Json::Array(elements) => elements.get(pointer_index(&token)?)?,
+                _ => return None
+            };
+        }
+        Some(target)
+    }
+
+    /// Mutable counterpart of pointer, None when any segment is missing or the types don't line up.
+    /// A "-" as the last token against an array is the append position like in a JSON Patch "add":
+    /// a null element is pushed and returned, so `*doc.pointer_mut("/elements/-").unwrap() = value`
+    /// appends 'value', the null stays behind when nothing is written. Anywhere else "-" matches nothing, as in pointer
+    pub fn pointer_mut(&mut self, ptr: &str) -> Option<&mut Json> {
+        let tokens = pointer_tokens(ptr)?;
+        let mut target = self;
+        for (i, token) in tokens.iter().enumerate() {
+            target = match target {
+                Json::Object(members) => members.get_mut(token)?,
+                Json::Array(elements) => {
+                    if token == "-" && i + 1 == tokens.len() {
+                        elements.push(Json::Null);
+                        elements.last_mut()?
+                    } else {
+                        elements.get_mut(pointer_index(token)?)?
+                    }
+                },
+                _ => return None
+            };
+        }
+        Some(target)
+    }
+
+    /// Friendlier dotted path like "dummy.overview" or "elements.2", segments select object members
+    /// by key and array elements by index, the empty path is the whole document. There is no escaping,
+    /// so keys that contain a '.' can't be reached this way, use pointer("/a.b") for those
+    pub fn get_path(&self, path: &str) -> Option<&Json> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        let mut target = self;
+        for segment in path.split('.') {
+            target = match target {
+                Json::Object(members) => members.get(segment)?,
+                Json::Array(elements) => elements.get(pointer_index(segment)?)?,
+                _ => return None
+            };
+        }
+        Some(target)
+    }
+
+    /// get_path with a fallback, 'default' for any missing segment or a segment that doesn't fit
+    /// the type it is applied to
+    pub fn get_path_or<'a>(&'a self, path: &str, default: &'a Json) -> &'a Json {
+        self.get_path(path).unwrap_or(default)
+    }
+
+    /// Flat key/value view of the document using get_path style keys ("dummy.overview", "elements.0"):
+    /// member keys and array indexes joined by '.'. The values are the leaves, scalars plus empty
+    /// arrays and objects since those have nothing to descend into. A scalar document has the key "".
+    /// Keys that contain a '.' or look like an index don't survive unflatten unchanged
+    pub fn flatten(&self) -> HashMap<String, Json> {
+        let mut flat = HashMap::new();
+        flatten_into(self, String::new(), &mut flat);
+        flat
+    }
+
+    /// Rebuild a nested document from flatten's output. Keys are applied in sorted order, a segment
+    /// that is a plain decimal number creates an array (missing elements are padded with null) and
+    /// any other segment an object. When an earlier key left a value of the other shape in the way,
+    /// e.g. "a" and "a.b", the later key replaces it
+    pub fn unflatten(flat: HashMap<String, Json>) -> Json {
+        let mut entries: Vec<(String, Json)> = flat.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let mut root = Json::Null;
+        for (key, value) in entries {
+            if key.is_empty() {
+                root = value;
+                continue;
+            }
+            let mut target = &mut root;
+            for segment in key.split('.') {
+                target = match pointer_index(segment) {
+                    Some(i) => {
+                        if !target.is_array() {
+                            *target = Json::Array(Vec::new());
+                        }
+                        let Json::Array(elements) = target else { unreachable!() };
+                        if elements.len() <= i {
+                            elements.resize(i + 1, Json::Null);
+                        }
+                        &mut elements[i]
+                    },
+                    None => {
+                        if !target.is_object() {
+                            *target = Json::Object(Map::default());
+                        }
+                        let Json::Object(members) = target else { unreachable!() };
+                        members.entry(segment.to_string()).or_insert(Json::Null)
+                    }
+                };
+            }
+            *target = value;
+        }
+        root
+    }
+
+    /// True when 'needle' is == to self or to any value nested in it, at any depth. Stops at the
+    /// first match, numbers compare by value so 2 finds 2.0. Object keys aren't values and never match
+    pub fn deep_contains(&self, needle: &Json) -> bool {
+        self == needle || match self {
+            Json::Array(elements) => elements.iter().any(|element| element.deep_contains(needle)),
+            Json::Object(members) => members.values().any(|value| value.deep_contains(needle)),
+            _ => false
+        }
+    }
+
+    /// JSON Pointers of every value that is == to 'needle', "" when self matches. They come in the order
+    /// walk visits the values: array elements by index and object members in map iteration order,
+    /// so sort the result when it has to be deterministic without preserve_order
+    pub fn find_paths(&self, needle: &Json) -> Vec<String> {
+        let mut paths = Vec::new();
+        find_paths_into(self, needle, String::new(), &mut paths);
+        paths
+    }
+}
+
+fn flatten_into(value: &Json, path: String, flat: &mut HashMap<String, Json>) {
+    let child = |segment: &str| if path.is_empty() { segment.to_string() } else { format!("{}.{}", path, segment) };
+    match value {
+        Json::Array(elements) if !elements.is_empty() => {
+            for (i, element) in elements.iter().enumerate() {
+                flatten_into(element, child(&i.to_string()), flat);
+            }
+        },
+        Json::Object(members) if !members.is_empty() => {
+            for (key, member) in members.iter() {
+                flatten_into(member, child(key), flat);
+            }
+        },
+        leaf => {
+            flat.insert(path, leaf.clone());
+        }
+    }
+}
+
+fn find_paths_into(value: &Json, needle: &Json, path: String, paths: &mut Vec<String>) {
+    if value == needle {
+        paths.push(path.clone());
+    }
+    match value {
+        Json::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                find_paths_into(element, needle, pointer_push(&path, &i.to_string()), paths);
+            }
+        },
+        Json::Object(members) => {
+            for (key, member) in members.iter() {
+                find_paths_into(member, needle, pointer_push(&path, key), paths);
+            }
+        },
+        _ => {}
+    }
+}
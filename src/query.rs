@@ -0,0 +1,88 @@
+use std::fmt;
+
+use crate::Json;
+
+/// Error for a query expression outside the supported grammar, 'offset' is the byte position
+/// in the expression where the problem starts
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueryError {
+    pub offset: usize,
+    pub reason: &'static str
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at offset {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+// One step of a query, applied to every value the previous steps selected
+enum Selector<'e> {
+    Member(&'e str),
+    Index(usize),
+    Wildcard
+}
+
+// Split the expression into selectors up front so bad syntax is an error even when nothing matches
+fn parse_query(expr: &str) -> Result<Vec<Selector<'_>>, QueryError> {
+    let error = |offset, reason| QueryError { offset, reason };
+    let mut rest = expr.strip_prefix('$').ok_or(error(0, "a query starts with '$'"))?;
+    let mut selectors = Vec::new();
+    while !rest.is_empty() {
+        let offset = expr.len() - rest.len();
+        if let Some(after) = rest.strip_prefix(".*") {
+            selectors.push(Selector::Wildcard);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[', ']', '(', ')', '{', '}']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(error(offset + 1, "expected a member name after '.'"));
+            }
+            if let Some(b']' | b'(' | b')' | b'{' | b'}') = after.as_bytes().get(end) {
+                return Err(error(offset + 1 + end, "unsupported bracket in a member name"));
+            }
+            selectors.push(Selector::Member(&after[..end]));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or(error(offset, "'[' without a matching ']'"))?;
+            let inside = &after[..end];
+            if inside == "*" {
+                selectors.push(Selector::Wildcard);
+            } else if !inside.is_empty() && inside.bytes().all(|b| b.is_ascii_digit()) {
+                selectors.push(Selector::Index(inside.parse().map_err(|_| error(offset + 1, "index out of range"))?));
+            } else {
+                return Err(error(offset + 1, "expected an index or '*' inside '[]'"));
+            }
+            rest = &after[end + 1..];
+        } else {
+            return Err(error(offset, "expected '.' or '['"));
+        }
+    }
+    Ok(selectors)
+}
+
+impl Json {
+    /// Select values with a small subset of JSONPath: '$' is the document, '.key' a member,
+    /// '[n]' an array element and '.*' or '[*]' every child of an array or object. Member names
+    /// run up to the next '.' or '[' and have no quoting or escapes, any other bracket in one is
+    /// an error. Selectors that don't fit
+    /// the value (a member of an array, a missing key) select nothing. Wildcards over objects
+    /// follow map iteration order, which is arbitrary unless preserve_order is enabled
+    pub fn query(&self, expr: &str) -> Result<Vec<&Json>, QueryError> {
+        let mut selected = vec![ self ];
+        for selector in parse_query(expr)? {
+            selected = selected.into_iter().flat_map(|value| -> Vec<&Json> {
+                match (&selector, value) {
+                    (Selector::Member(key), _) => value.get(key).into_iter().collect(),
+                    (Selector::Index(i), _) => value.get_index(*i).into_iter().collect(),
+                    (Selector::Wildcard, Json::Array(elements)) => elements.iter().collect(),
+                    (Selector::Wildcard, Json::Object(members)) => members.values().collect(),
+                    (Selector::Wildcard, _) => Vec::new()
+                }
+            }).collect();
+        }
+        Ok(selected)
+    }
+}
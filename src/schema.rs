@@ -0,0 +1,81 @@
+use std::fmt;
+
+use crate::pointer::pointer_push;
+use crate::Json;
+
+/// One mismatch found by Json::validate_against, 'path' is the JSON Pointer of the offending value
+/// in the document (empty for the root) and 'message' says what the schema expected there
+#[derive(Clone, PartialEq, Debug)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "validation failed at \"{}\": {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+// Whether a value has the type named in a schema, None for a name outside the dialect
+fn has_type(value: &Json, name: &str) -> Option<bool> {
+    Some(match name {
+        "null" => value.is_null(),
+        "bool" => matches!(value, Json::Boolean(_)),
+        "number" => matches!(value, Json::Number(_) | Json::Integer(_)),
+        "string" => matches!(value, Json::String(_)),
+        "array" => matches!(value, Json::Array(_)),
+        "object" => matches!(value, Json::Object(_)),
+        _ => return None
+    })
+}
+
+fn validate(value: &Json, schema: &Json, path: &str, errors: &mut Vec<ValidationError>) {
+    let mut error = |message: String| errors.push(ValidationError { path: path.to_string(), message });
+    let Json::Object(keywords) = schema else {
+        return error(format!("schema must be an object, found {}", schema.type_name()));
+    };
+    if let Some(expected) = keywords.get("type") {
+        match expected.as_str().and_then(|name| Some((name, has_type(value, name)?))) {
+            Some((_, true)) => {},
+            Some((name, false)) => error(format!("expected {}, found {}", name, value.type_name())),
+            None => error(format!("unknown schema type {}", expected))
+        }
+    }
+    if let (Some(required), Json::Object(members)) = (keywords.get("required"), value) {
+        match required.as_array().filter(|keys| keys.iter().all(Json::is_string)) {
+            Some(keys) => {
+                for key in keys.iter().filter_map(Json::as_str).filter(|key| !members.contains_key(*key)) {
+                    error(format!("missing required member \"{}\"", key));
+                }
+            },
+            None => error("schema \"required\" must be an array of strings".to_string())
+        }
+    }
+    if let (Some(items), Json::Array(elements)) = (keywords.get("items"), value) {
+        for (i, element) in elements.iter().enumerate() {
+            validate(element, items, &pointer_push(path, &i.to_string()), errors);
+        }
+    }
+}
+
+impl Json {
+    /// Check the document against a schema in a deliberately tiny dialect, a schema is an object
+    /// with any of these keywords and every other member is ignored:
+    ///
+    /// - `"type"`: one of `"null"`, `"bool"`, `"number"`, `"string"`, `"array"` or `"object"`,
+    ///   "number" matches integers and floats alike
+    /// - `"required"`: an array of member names that must be present, checked only when the value is an object
+    /// - `"items"`: a schema every element must satisfy, checked only when the value is an array
+    ///
+    /// Every mismatch is collected instead of stopping at the first one. A malformed schema
+    /// (not an object, an unknown type name, "required" not a list of strings) is reported as
+    /// an error at the path where it was applied
+    pub fn validate_against(&self, schema: &Json) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        validate(self, schema, "", &mut errors);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
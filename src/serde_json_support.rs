@@ -0,0 +1,35 @@
+use crate::Json;
+use serde_json::Value;
+
+// Integers that fit an i64 stay Json::Integer, larger u64 values and all floats become
+// Json::Number, the same split the parser makes
+impl From<Value> for Json {
+    fn from(v: Value) -> Json {
+        match v {
+            Value::Null => Json::Null,
+            Value::Bool(b) => Json::Boolean(b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Json::Integer(i),
+                None => Json::Number(n.as_f64().unwrap_or(f64::NAN))
+            },
+            Value::String(s) => Json::String(s),
+            Value::Array(elements) => Json::Array(elements.into_iter().map(Json::from).collect()),
+            Value::Object(members) => Json::Object(members.into_iter().map(|(k, v)| (k, Json::from(v))).collect())
+        }
+    }
+}
+
+// serde_json can't hold NaN or infinity, like serde_json itself those become Value::Null
+impl From<Json> for Value {
+    fn from(j: Json) -> Value {
+        match j {
+            Json::Null => Value::Null,
+            Json::Boolean(b) => Value::Bool(b),
+            Json::Number(n) => serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number),
+            Json::Integer(i) => Value::Number(i.into()),
+            Json::String(s) => Value::String(s),
+            Json::Array(elements) => Value::Array(elements.into_iter().map(Value::from).collect()),
+            Json::Object(members) => Value::Object(members.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::{Json, Map};
+
+// Write a string as a double-quoted JSON string literal with all required escapes
+pub(crate) fn write_escaped_str<W: fmt::Write>(w: &mut W, s: &str) -> fmt::Result {
+    write_escaped_str_with(w, s, &SerializeOptions::default())
+}
+
+// Same with the optional escapes of SerializeOptions on top of the required ones
+fn write_escaped_str_with<W: fmt::Write>(w: &mut W, s: &str, options: &SerializeOptions) -> fmt::Result {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '/' if options.escape_solidus => w.write_str("\\/")?,
+            '<' | '>' | '&' if options.escape_html => write!(w, "\\u{:04x}", c as u32)?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            '\u{08}' => w.write_str("\\b")?,
+            '\u{0c}' => w.write_str("\\f")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?
+        }
+    }
+    w.write_char('"')
+}
+
+// Largest magnitude up to which every integer is exactly representable as an f64
+const MAX_SAFE_INTEGER: f64 = 9007199254740992.0;
+
+// Write a number, JSON has no representation for NaN or infinity so these become null.
+// Integral values in the safe range are written without a fraction (5.0 as 5), larger integral
+// values and ones closer to zero than 1e-5 use an exponent (1e20, 1e-300) instead of a long run
+// of zeros. Both {} and {:e} print the shortest digits that parse back to the same f64, so every
+// finite number round trips exactly, see the float round trip property test
+pub(crate) fn write_number<W: fmt::Write>(w: &mut W, n: f64) -> fmt::Result {
+    if !n.is_finite() {
+        w.write_str("null")
+    } else if n.fract() == 0.0 && n.abs() > MAX_SAFE_INTEGER || n != 0.0 && n.abs() < 1e-5 {
+        write!(w, "{:e}", n)
+    } else {
+        write!(w, "{}", n)
+    }
+}
+
+// With a precision the number is rounded to that many significant digits first, then written
+// like any other number so integral results still have no fraction
+fn write_rounded_number<W: fmt::Write>(w: &mut W, n: f64, precision: Option<usize>) -> fmt::Result {
+    match precision {
+        Some(digits) if n.is_finite() => {
+            let rounded = format!("{:.*e}", digits.max(1) - 1, n).parse().expect("formatted f64 parses back");
+            write_number(w, rounded)
+        },
+        _ => write_number(w, n)
+    }
+}
+
+/// Settings for to_string_with_options, the defaults give the same text as to_string
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct SerializeOptions {
+    /// Pretty print with this many spaces per nesting level like to_pretty_string, None is compact
+    pub indent: Option<usize>,
+    /// Round every Json::Number to this many significant digits (0 counts as 1) before writing it,
+    /// None writes the shortest text that parses back to the same f64. Integers are always exact
+    pub number_precision: Option<usize>,
+    /// Write '/' as "\/" in strings and keys, off by default since JSON doesn't require it. Inside an
+    /// HTML `<script>` element this keeps a "</script>" in the data from ending the element
+    pub escape_solidus: bool,
+    /// Write '<', '>' and '&' as \u003c, \u003e and \u0026 in strings and keys, off by default.
+    /// The text then can't open or close HTML tags, however it is embedded
+    pub escape_html: bool
+}
+
+impl SerializeOptions {
+    fn pretty(indent: usize) -> Self {
+        SerializeOptions { indent: Some(indent), ..SerializeOptions::default() }
+    }
+}
+
+// Compact JSON text without any insignificant whitespace. For every value without NaN or infinity
+// Json::from_str(&value.to_string()) gives back an equal value, an integral Number may come back as
+// the equal Integer, see the round trip property test. The alternate flag pretty prints like
+// to_pretty_string, indented by the width if one is given: {:#} uses 2 spaces and {:#4} uses 4
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return self.write_with_options(f, &SerializeOptions::pretty(f.width().unwrap_or(2)), 0);
+        }
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Boolean(b) => write!(f, "{}", b),
+            Json::Number(n) => write_number(f, *n),
+            Json::Integer(i) => write!(f, "{}", i),
+            Json::String(s) => write_escaped_str(f, s),
+            Json::Array(elements) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                f.write_str("]")
+            },
+            Json::Object(members) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_escaped_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+// Same layout as a derived Debug, including {:#?}, except that object members are sorted by key
+// instead of following the HashMap's order, which changes from run to run
+impl fmt::Debug for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("Null"),
+            Json::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            Json::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            Json::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Json::String(s) => f.debug_tuple("String").field(s).finish(),
+            Json::Array(elements) => f.debug_tuple("Array").field(elements).finish(),
+            Json::Object(members) => f.debug_tuple("Object").field(&SortedMembers(members)).finish()
+        }
+    }
+}
+
+struct SortedMembers<'a>(&'a Map<String, Json>);
+
+impl fmt::Debug for SortedMembers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut members: Vec<(&String, &Json)> = self.0.iter().collect();
+        members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        f.debug_map().entries(members).finish()
+    }
+}
+
+// Adapter that lets the fmt::Write based serializers stream into an io::Write,
+// fmt::Error carries no details so the underlying io::Error is kept on the side
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+fn write_io<W: io::Write>(w: &mut W, write: impl FnOnce(&mut IoAdapter<'_, W>) -> fmt::Result) -> io::Result<()> {
+    let mut adapter = IoAdapter { inner: w, error: None };
+    match write(&mut adapter) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(adapter.error.unwrap_or_else(|| io::Error::other("JSON formatting failed")))
+    }
+}
+
+/// Error from the checked serializer for numbers that JSON text cannot represent
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NonFiniteError {
+    pub value: f64
+}
+
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "number {} has no JSON representation", self.value)
+    }
+}
+
+impl std::error::Error for NonFiniteError {}
+
+impl Json {
+    // The first NaN or infinite number in depth-first order, if any
+    fn find_non_finite(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) if !n.is_finite() => Some(*n),
+            Json::Array(elements) => elements.iter().find_map(Json::find_non_finite),
+            Json::Object(members) => members.values().find_map(Json::find_non_finite),
+            _ => None
+        }
+    }
+
+    /// Checked compact JSON text. Display / to_string is the lossy default and writes NaN and
+    /// infinity as null like JavaScript's JSON.stringify, this fails on them instead
+    pub fn try_to_string(&self) -> Result<String, NonFiniteError> {
+        match self.find_non_finite() {
+            Some(value) => Err(NonFiniteError { value }),
+            None => Ok(self.to_string())
+        }
+    }
+
+    /// Plain text of a scalar for templates and messages: strings as they are without quotes or
+    /// escapes, numbers and booleans as in JSON text and null as "null". NaN and infinite numbers
+    /// become "NaN", "inf" and "-inf" instead of the null Display writes. None for arrays and objects
+    pub fn coerce_to_string(&self) -> Option<String> {
+        match self {
+            Json::String(s) => Some(s.clone()),
+            Json::Number(n) if !n.is_finite() => Some(n.to_string()),
+            Json::Array(_) | Json::Object(_) => None,
+            scalar => Some(scalar.to_string())
+        }
+    }
+
+    /// Newline-delimited JSON, every value in compact form followed by '\n'
+    pub fn to_ndjson(values: &[Json]) -> String {
+        let mut out = String::new();
+        for value in values {
+            out.push_str(&value.to_string());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Human readable JSON text with 'indent' spaces per nesting level
+    pub fn to_pretty_string(&self, indent: usize) -> String {
+        let mut out = String::new();
+        self.write_with_options(&mut out, &SerializeOptions::pretty(indent), 0).expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Stream compact JSON text into a writer without building a String first
+    pub fn to_writer<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write_io(w, |out| fmt::Write::write_fmt(out, format_args!("{}", self)))
+    }
+
+    /// Stream pretty printed JSON text into a writer, same layout as to_pretty_string
+    pub fn to_writer_pretty<W: io::Write>(&self, w: &mut W, indent: usize) -> io::Result<()> {
+        write_io(w, |out| self.write_with_options(out, &SerializeOptions::pretty(indent), 0))
+    }
+
+    /// Write compact JSON text to a file, creating or truncating it, streamed through a buffer
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut w)?;
+        w.flush()
+    }
+
+    /// Same as to_file with the layout of to_pretty_string
+    pub fn to_file_pretty<P: AsRef<Path>>(&self, path: P, indent: usize) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.to_writer_pretty(&mut w, indent)?;
+        w.flush()
+    }
+
+    /// Compact JSON text as UTF-8 bytes, written straight into the Vec
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.to_writer(&mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Pretty printed JSON text as UTF-8 bytes, same layout as to_pretty_string
+    pub fn to_vec_pretty(&self, indent: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.to_writer_pretty(&mut out, indent).expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// JSON text written with explicit settings, see SerializeOptions
+    pub fn to_string_with_options(&self, options: SerializeOptions) -> String {
+        let mut out = String::new();
+        self.write_with_options(&mut out, &options, 0).expect("writing to a String cannot fail");
+        out
+    }
+
+    // Compact or pretty depending on 'options.indent', 'level' is the nesting level of self
+    fn write_with_options<W: fmt::Write>(&self, w: &mut W, options: &SerializeOptions, level: usize) -> fmt::Result {
+        // Pretty printing puts every item on its own line, indented by its nesting level
+        let newline = |w: &mut W, level: usize| match options.indent {
+            Some(indent) => write!(w, "\n{:1$}", "", indent * level),
+            None => Ok(())
+        };
+        match self {
+            Json::Number(n) => write_rounded_number(w, *n, options.number_precision),
+            Json::String(s) => write_escaped_str_with(w, s, options),
+            Json::Array(elements) if !elements.is_empty() => {
+                w.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        w.write_str(",")?;
+                    }
+                    newline(w, level + 1)?;
+                    element.write_with_options(w, options, level + 1)?;
+                }
+                newline(w, level)?;
+                w.write_str("]")
+            },
+            Json::Object(members) if !members.is_empty() => {
+                w.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        w.write_str(",")?;
+                    }
+                    newline(w, level + 1)?;
+                    write_escaped_str_with(w, key, options)?;
+                    w.write_str(if options.indent.is_some() { ": " } else { ":" })?;
+                    value.write_with_options(w, options, level + 1)?;
+                }
+                newline(w, level)?;
+                w.write_str("}")
+            },
+            // Other scalars and empty containers look the same in every layout
+            other => write!(w, "{}", other)
+        }
+    }
+
+    /// Deterministic compact JSON text for hashing and signing, values that are == give the same bytes.
+    /// Object keys are sorted by code point on every level. Every number that as_i64 can represent,
+    /// whether Integer or Number (including -0.0), is written as integer text, any other finite number
+    /// in Rust's shortest exponent form like 1.5e0 or 1e20, and NaN and infinity as null
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out).expect("writing to a String cannot fail");
+        out
+    }
+
+    fn write_canonical<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        match self {
+            Json::Number(n) => match self.as_i64() {
+                Some(i) => write!(w, "{}", i),
+                None if n.is_finite() => write!(w, "{:e}", n),
+                None => w.write_str("null")
+            },
+            Json::Array(elements) => {
+                w.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        w.write_str(",")?;
+                    }
+                    element.write_canonical(w)?;
+                }
+                w.write_str("]")
+            },
+            Json::Object(members) => {
+                let mut sorted: Vec<(&String, &Json)> = members.iter().collect();
+                sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                w.write_str("{")?;
+                for (i, (key, value)) in sorted.into_iter().enumerate() {
+                    if i > 0 {
+                        w.write_str(",")?;
+                    }
+                    write_escaped_str(w, key)?;
+                    w.write_str(":")?;
+                    value.write_canonical(w)?;
+                }
+                w.write_str("}")
+            },
+            other => write!(w, "{}", other)
+        }
+    }
+}
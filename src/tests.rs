@@ -0,0 +1,2072 @@
+use super::*;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+#[test]
+fn test_null_json() {
+    let res = json!( null );
+    assert_eq!(res, Json::Null);
+}
+
+#[test]
+fn test_empty_object() {
+    let res = json!( {} );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 0 as usize)
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_single_element_object() {
+    let res = json!( { "test" : 1} );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 1 as usize);
+            let elements = a;
+            let el1 = elements.get("test");
+            match el1 {
+                Some( x) =>
+                    match x {
+                        Json::Integer(v) => assert_eq!(v, &1),
+                        _ => assert!(false)
+                    }
+                _ => assert!(false)
+            }
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_single_element_object_boolean() {
+    let res = json!( { "test" : false} );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 1 as usize);
+            let elements = a;
+            let el1 = elements.get("test");
+            match el1 {
+                Some( x) =>
+                    match x {
+                        Json::Boolean(v) => assert_eq!(v, &false),
+                        _ => assert!(false)
+                    }
+                _ => assert!(false)
+            }
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_single_element_object_string() {
+    let res = json!( { "test" : "yes"} );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 1 as usize);
+            let elements = a;
+            let el1 = elements.get("test");
+            match el1 {
+                Some( x) =>
+                    match x {
+                        Json::String(v) => assert_eq!(v, &"yes"),
+                        _ => assert!(false)
+                    }
+                _ => assert!(false)
+            }
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_single_element_object_array() {
+    let res = json!( { "test" : [ "yes" ] } );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 1 as usize);
+            let elements = a;
+            let el1 = elements.get("test");
+            match el1 {
+                Some( x) =>
+                    match x {
+                        Json::Array(v) => {
+                            assert_eq!((*v).len(), 1);
+                            match  v.first() {
+                                Some (e) => assert_eq!( e, &Json::String("yes".to_string()) ),
+                                _ => assert!(false)
+                            }
+                        },
+                        _ => assert!(false)
+                    }
+                _ => assert!(false)
+            }
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_single_element_object_object() {
+    let res = json!( { "test" : { "tall" : 1.0 } } );
+    match res {
+        Json::Object( a) => {
+            assert_eq!(a.len(), 1 as usize);
+            let elements = a;
+            let el1 = elements.get("test");
+            match el1 {
+                Some( x) =>
+                    match x {
+                        Json::Object(v) => {
+                            assert_eq!((*v).len(), 1);
+                            match  v.get("tall") {
+                                Some (e) => assert_eq!( e, &Json::Number(1.0) ),
+                                _ => assert!(false)
+                            }
+                        },
+                        _ => assert!(false)
+                    }
+                _ => assert!(false)
+            }
+        },
+        _ => assert!(false)
+    }
+}
+
+#[test]
+fn test_constructors() {
+    let mut root = Json::object();
+    root.insert("width", Json::number(100.0));
+    root.insert("name", Json::string("dummy"));
+    root.insert("elements", Json::array(vec![ Json::bool(true), Json::null() ]));
+    root.insert("dummy", Json::object());
+    assert_eq!(root, json!( { "width" : 100.0, "name" : "dummy", "elements" : [ true, null ], "dummy" : {} } ));
+    assert!(matches!(Json::number(1.0), Json::Number(_)));
+    assert_eq!(Json::string(String::from("s")), Json::String(String::from("s")));
+}
+
+#[test]
+fn test_try_number() {
+    assert!(matches!(Json::try_number(2.5), Ok(Json::Number(n)) if n == 2.5));
+    assert!(Json::try_number(f64::NAN).unwrap_err().value.is_nan());
+    assert_eq!(Json::try_number(f64::INFINITY), Err(NonFiniteError { value: f64::INFINITY }));
+    assert_eq!(Json::try_number(f64::NEG_INFINITY), Err(NonFiniteError { value: f64::NEG_INFINITY }));
+    assert!(matches!(Json::from(f64::NAN), Json::Number(n) if n.is_nan()));
+}
+
+#[test]
+fn test_large_object_literal() {
+    let res = json!( { "k0" : 0, "k1" : 1, "k2" : 2, "k3" : 3, "k4" : 4, "k5" : 5, "k6" : 6, "k7" : 7, "k8" : 8, "k9" : 9, "k10" : 10, "k11" : 11, "k12" : 12, "k13" : 13, "k14" : 14, "k15" : 15, "k16" : 16, "k17" : 17, "k18" : 18, "k19" : 19, "k20" : 20, "k21" : 21, "k22" : 22, "k23" : 23, "k24" : 24, "k25" : 25, "k26" : 26, "k27" : 27, "k28" : 28, "k29" : 29, "k30" : 30, "k31" : 31 } );
+    assert_eq!(res.len(), Some(32));
+    assert_eq!(res["k0"], json!( 0 ));
+    assert_eq!(res["k31"], json!( 31 ));
+
+    let members = [ 1, 2 ];
+    let res = json!( { "a" : 1, "members" : members, "a" : [ 2, 3 ], } );
+    assert_eq!(res, json!( { "a" : [ 2, 3 ], "members" : [ 1, 2 ] } ));
+}
+
+#[test]
+fn test_accessors_matching_variant() {
+    assert_eq!(json!( "yes" ).as_str(), Some("yes"));
+    assert_eq!(json!( 480.0 ).as_f64(), Some(480.0));
+    assert_eq!(json!( true ).as_bool(), Some(true));
+    assert_eq!(json!( [ 1, 2 ] ).as_array(), Some(&vec![ Json::Integer(1), Json::Integer(2) ]));
+    let res = json!( { "width" : 100 } );
+    assert_eq!(res.as_object().and_then(|o| o.get("width")).and_then(Json::as_f64), Some(100.0));
+}
+
+#[test]
+fn test_integer_accessors() {
+    assert_eq!(json!( 42 ).as_i64(), Some(42));
+    assert_eq!(json!( 42.0 ).as_u64(), Some(42));
+    assert_eq!(Json::from(-3).as_i64(), Some(-3));
+    assert_eq!(Json::from(-3).as_u64(), None);
+    assert_eq!(Json::from(i64::MIN).as_i64(), Some(i64::MIN));
+    assert_eq!(Json::from(1u64 << 63).as_u64(), Some(1 << 63));
+    assert_eq!(Json::from(1u64 << 63).as_i64(), None);
+    assert_eq!(Json::from(u64::MAX).as_u64(), None);
+    assert_eq!(json!( 1.5 ).as_i64(), None);
+    assert_eq!(json!( 9223372036854775808.0 ).as_i64(), None);
+    assert_eq!(json!( 18446744073709551616.0 ).as_u64(), None);
+    assert_eq!(Json::from(f64::NAN).as_i64(), None);
+    assert_eq!(Json::from(f64::INFINITY).as_u64(), None);
+    assert_eq!(json!( "1" ).as_i64(), None);
+}
+
+#[test]
+fn test_accessors_other_variant() {
+    let res = json!( null );
+    assert_eq!(res.as_str(), None);
+    assert_eq!(res.as_f64(), None);
+    assert_eq!(res.as_bool(), None);
+    assert_eq!(res.as_array(), None);
+    assert_eq!(res.as_object(), None);
+    assert_eq!(json!( "1" ).as_f64(), None);
+    assert_eq!(json!( [] ).as_object(), None);
+}
+
+#[test]
+fn test_predicates() {
+    let values = [ json!( null ), json!( false ), json!( 1 ), json!( "a" ), json!( [] ), json!( {} ) ];
+    let checks: [fn(&Json) -> bool; 6] = [ Json::is_null, Json::is_boolean, Json::is_number, Json::is_string, Json::is_array, Json::is_object ];
+    for (i, value) in values.iter().enumerate() {
+        for (j, check) in checks.iter().enumerate() {
+            assert_eq!(check(value), i == j, "predicate {} on {:?}", j, value);
+        }
+    }
+}
+
+#[test]
+fn test_get() {
+    let res = json!( { "width" : 100, "dummy" : { "overview" : true } } );
+    assert_eq!(res.get("width").and_then(Json::as_f64), Some(100.0));
+    assert_eq!(res.get("dummy").and_then(|d| d.get("overview")), Some(&Json::Boolean(true)));
+    assert_eq!(res.get("height"), None);
+    assert_eq!(json!( [ 1 ] ).get("width"), None);
+}
+
+#[test]
+fn test_get_index() {
+    let res = json!( [ 1, 2, 3, 43, 5 ] );
+    assert_eq!(res.get_index(3), Some(&Json::Integer(43)));
+    assert_eq!(res.get_index(5), None);
+    assert_eq!(json!( { "0" : 1 } ).get_index(0), None);
+}
+
+#[test]
+fn test_index() {
+    let res = json!( { "width" : 100, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    assert_eq!(res["width"], Json::Integer(100));
+    assert_eq!(res["elements"][3], Json::Integer(43));
+    assert_eq!(res["dummy"]["overview"], Json::Boolean(true));
+}
+
+#[test]
+fn test_index_missing_is_null() {
+    let res = json!( { "elements" : [ 1 ] } );
+    assert_eq!(res["height"], Json::Null);
+    assert_eq!(res["a"]["b"]["c"], Json::Null);
+    assert_eq!(res["elements"][7], Json::Null);
+    assert_eq!(res["elements"]["x"], Json::Null);
+    assert_eq!(res[0], Json::Null);
+}
+
+#[test]
+fn test_get_mut() {
+    let mut root = json!( { "width" : 100, "elements" : [ 1, 2 ] } );
+    if let Some(v) = root.get_mut("width") {
+        *v = Json::from(200);
+    }
+    if let Some(v) = root.get_mut("elements").and_then(|e| e.get_index_mut(1)) {
+        *v = Json::from("two");
+    }
+    assert_eq!(root, json!( { "width" : 200, "elements" : [ 1, "two" ] } ));
+    assert_eq!(root.get_mut("height"), None);
+    assert_eq!(root.get_index_mut(0), None);
+}
+
+#[test]
+fn test_index_mut() {
+    let mut root = json!( { "dummy" : { "overview" : true }, "elements" : [ 1, 2 ] } );
+    root["newkey"] = json!( 5 );
+    root["dummy"]["overview"] = json!( false );
+    root["elements"][0] = json!( null );
+    assert_eq!(root, json!( { "dummy" : { "overview" : false }, "elements" : [ null, 2 ], "newkey" : 5 } ));
+}
+
+#[test]
+#[should_panic]
+fn test_index_mut_non_object_panics() {
+    let mut root = json!( [ 1 ] );
+    root["key"] = json!( 1 );
+}
+
+#[test]
+#[should_panic]
+fn test_index_mut_out_of_range_panics() {
+    let mut root = json!( [ 1 ] );
+    root[1] = json!( 2 );
+}
+
+#[test]
+fn test_pointer() {
+    let res = json!( { "width" : 100, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    assert_eq!(res.pointer(""), Some(&res));
+    assert_eq!(res.pointer("/elements/2"), Some(&Json::Integer(3)));
+    assert_eq!(res.pointer("/dummy/overview"), Some(&Json::Boolean(true)));
+    assert_eq!(res.pointer("/elements/5"), None);
+    assert_eq!(res.pointer("/elements/01"), None);
+    assert_eq!(res.pointer("/elements/-"), None);
+    assert_eq!(res.pointer("/height"), None);
+    assert_eq!(res.pointer("/width/0"), None);
+    assert_eq!(res.pointer("width"), None);
+}
+
+#[test]
+fn test_pointer_rfc6901_escapes() {
+    let res = json!( { "a/b" : 1, "m~n" : 2, "" : 3, "~1" : 4 } );
+    assert_eq!(res.pointer("/a~1b"), Some(&Json::Integer(1)));
+    assert_eq!(res.pointer("/m~0n"), Some(&Json::Integer(2)));
+    assert_eq!(res.pointer("/"), Some(&Json::Integer(3)));
+    assert_eq!(res.pointer("/~01"), Some(&Json::Integer(4)));
+}
+
+#[test]
+fn test_pointer_mut() {
+    let mut doc = json!( { "dummy" : { "overview" : true, "list" : [ { "deep" : 1 } ] } } );
+    *doc.pointer_mut("/dummy/overview").unwrap() = Json::from(false);
+    *doc.pointer_mut("/dummy/list/0/deep").unwrap() = Json::from("changed");
+    assert_eq!(doc, json!( { "dummy" : { "overview" : false, "list" : [ { "deep" : "changed" } ] } } ));
+}
+
+#[test]
+fn test_pointer_mut_append_token() {
+    let mut doc = json!( { "arr" : [ 1, 2 ], "dummy" : { "-" : 0 } } );
+    *doc.pointer_mut("/arr/-").unwrap() = json!( { "id" : 3 } );
+    assert_eq!(doc["arr"], json!( [ 1, 2, { "id" : 3 } ] ));
+    assert_eq!(doc.pointer("/arr/-"), None);
+    assert_eq!(doc.pointer_mut("/arr/-/id"), None);
+    assert_eq!(doc["arr"].len(), Some(3));
+    assert_eq!(doc.pointer_mut("/arr/-"), Some(&mut Json::Null));
+    assert_eq!(doc["arr"], json!( [ 1, 2, { "id" : 3 }, null ] ));
+    assert_eq!(doc["arr"].pop(), Some(Json::Null));
+    *doc.pointer_mut("/dummy/-").unwrap() = json!( 1 );
+    assert_eq!(doc["dummy"], json!( { "-" : 1 } ));
+
+    doc.apply_patch(&json!( [ { "op" : "add", "path" : "/arr/-", "value" : 4 } ] )).unwrap();
+    assert_eq!(doc["arr"], json!( [ 1, 2, { "id" : 3 }, 4 ] ));
+    let err = doc.apply_patch(&json!( [ { "op" : "replace", "path" : "/arr/-", "value" : 5 } ] ));
+    assert_eq!(err, Err(PatchError::PathNotFound("/arr/-".to_string())));
+    assert_eq!(doc["arr"].len(), Some(4));
+}
+
+#[test]
+fn test_pointer_mut_missing() {
+    let mut doc = json!( { "dummy" : { "list" : [ 1 ] } } );
+    assert_eq!(doc.pointer_mut("/dummy/missing/x"), None);
+    assert_eq!(doc.pointer_mut("/dummy/list/1"), None);
+    assert_eq!(doc.pointer_mut("/dummy/list/0/x"), None);
+    assert_eq!(doc.pointer_mut("dummy"), None);
+    assert!(doc.pointer_mut("").is_some());
+}
+
+#[test]
+fn test_deep_contains() {
+    let doc = json!( { "id" : 7, "rows" : [ { "id" : 7, "tags" : [ "x" ] }, { "id" : 8.0, "child" : { "id" : 7.0 } } ], "id~/x" : [ "x" ] } );
+    assert!(doc.deep_contains(&json!( 7 )));
+    assert!(doc.deep_contains(&json!( 8 )));
+    assert!(doc.deep_contains(&json!( [ "x" ] )));
+    assert!(doc.deep_contains(&json!( { "id" : 7 } )));
+    assert!(doc.deep_contains(&doc));
+    assert!(!doc.deep_contains(&json!( "id" )));
+    assert!(!doc.deep_contains(&json!( 9 )));
+}
+
+#[test]
+fn test_find_paths() {
+    let doc = json!( { "id" : 7, "rows" : [ { "id" : 7, "tags" : [ "x" ] }, { "id" : 8.0, "child" : { "id" : 7.0 } } ], "id~/x" : [ "x" ] } );
+    let mut paths = doc.find_paths(&json!( 7 ));
+    paths.sort();
+    assert_eq!(paths, [ "/id", "/rows/0/id", "/rows/1/child/id" ]);
+    let mut paths = doc.find_paths(&json!( "x" ));
+    paths.sort();
+    assert_eq!(paths, [ "/id~0~1x/0", "/rows/0/tags/0" ]);
+    assert!(paths.iter().all(|path| doc.pointer(path) == Some(&json!( "x" ))));
+
+    assert_eq!(doc.find_paths(&json!( 9 )), Vec::<String>::new());
+    assert_eq!(json!( [ [], [ [] ] ] ).find_paths(&json!( [] )), [ "/0", "/1/0" ]);
+    assert_eq!(json!( [ [ 1 ], [ [ 1 ] ] ] ).find_paths(&json!( [ 1 ] )), [ "/0", "/1/0" ]);
+    assert_eq!(json!( 5 ).find_paths(&json!( 5.0 )), [ "" ]);
+}
+
+#[test]
+fn test_get_path() {
+    let doc = json!( { "elements" : [ 1, 2, { "name" : "x" } ], "dummy" : { "overview" : true }, "a.b" : 1 } );
+    assert_eq!(doc.get_path("dummy.overview"), Some(&Json::Boolean(true)));
+    assert_eq!(doc.get_path("elements.1"), Some(&Json::Integer(2)));
+    assert_eq!(doc.get_path("elements.2.name"), Some(&Json::from("x")));
+    assert_eq!(doc.get_path(""), Some(&doc));
+}
+
+#[test]
+fn test_get_path_missing() {
+    let doc = json!( { "elements" : [ 1 ], "dummy" : { "overview" : true }, "a.b" : 1 } );
+    assert_eq!(doc.get_path("elements.1"), None);
+    assert_eq!(doc.get_path("elements.x"), None);
+    assert_eq!(doc.get_path("dummy.overview.deeper"), None);
+    assert_eq!(doc.get_path("a.b"), None);
+    assert_eq!(doc.pointer("/a.b"), Some(&Json::Integer(1)));
+}
+
+#[test]
+fn test_validate_against() {
+    let desc = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    let schema = json!( {
+        "type" : "object",
+        "required" : [ "width", "height", "elements" ],
+        "elements" : "ignored",
+        "items" : { "type" : "string" }
+    } );
+    assert_eq!(desc.validate_against(&schema), Ok(()));
+    assert_eq!(desc["elements"].validate_against(&json!( { "type" : "array", "items" : { "type" : "number" } } )), Ok(()));
+
+    let errors = desc["elements"].validate_against(&json!( { "type" : "array", "items" : { "type" : "string" } } )).unwrap_err();
+    assert_eq!(errors.len(), 5);
+    assert_eq!(errors[3], ValidationError { path: "/3".to_string(), message: "expected string, found number".to_string() });
+
+    let errors = desc.validate_against(&json!( { "type" : "array", "required" : [ "depth", "width", "title" ] } )).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, [ "expected array, found object", "missing required member \"depth\"", "missing required member \"title\"" ]);
+    assert_eq!(errors[0].to_string(), "validation failed at \"\": expected array, found object");
+
+    let errors = json!( [ null, true ] ).validate_against(&json!( { "type" : "list", "items" : 5 } )).unwrap_err();
+    assert_eq!(errors.iter().map(|e| e.path.as_str()).collect::<Vec<_>>(), [ "", "/0", "/1" ]);
+    assert_eq!(errors[0].message, "unknown schema type \"list\"");
+    assert_eq!(errors[1].message, "schema must be an object, found number");
+}
+
+#[test]
+fn test_query() {
+    let desc = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true }, "rows" : [ { "id" : 1 }, { "id" : 2 }, { "name" : "x" } ] } );
+    let ints = |values: Vec<&Json>| values.iter().filter_map(|v| v.as_i64()).collect::<Vec<_>>();
+    assert_eq!(desc.query("$"), Ok(vec![ &desc ]));
+    assert_eq!(desc.query("$.dummy.overview"), Ok(vec![ &Json::Boolean(true) ]));
+    assert_eq!(desc.query("$.elements[3]"), Ok(vec![ &Json::Integer(43) ]));
+    assert_eq!(ints(desc.query("$.elements[*]").unwrap()), [ 1, 2, 3, 43, 5 ]);
+    assert_eq!(ints(desc.query("$.elements.*").unwrap()), [ 1, 2, 3, 43, 5 ]);
+    assert_eq!(ints(desc.query("$.rows[*].id").unwrap()), [ 1, 2 ]);
+    assert_eq!(desc.query("$.*").unwrap().len(), 5);
+    assert_eq!(desc.query("$.dummy[0]"), Ok(vec![]));
+    assert_eq!(desc.query("$.elements[9]"), Ok(vec![]));
+    assert_eq!(desc.query("$.width.*"), Ok(vec![]));
+}
+
+#[test]
+fn test_query_rejects_unsupported_syntax() {
+    let doc = json!( { "a" : [ 1 ] } );
+    assert_eq!(doc.query("a"), Err(QueryError { offset: 0, reason: "a query starts with '$'" }));
+    assert_eq!(doc.query("$..a").unwrap_err().offset, 2);
+    assert_eq!(doc.query("$.a[0").unwrap_err().offset, 3);
+    assert_eq!(doc.query("$.a[-1]").unwrap_err().offset, 4);
+    assert_eq!(doc.query("$.a['b']").unwrap_err().offset, 4);
+    assert_eq!(json!( { "a" : 1 } ).query("$.a]"), Err(QueryError { offset: 3, reason: "unsupported bracket in a member name" }));
+    assert_eq!(doc.query("$.a(0)").unwrap_err().offset, 3);
+    assert_eq!(doc.query("$.a[0].b}").unwrap_err().offset, 8);
+    assert_eq!(doc.query("$a").unwrap_err().to_string(), "invalid query at offset 1: expected '.' or '['");
+}
+
+#[test]
+fn test_get_or_defaults() {
+    static DEFAULT_PORT: Json = Json::Integer(8080);
+    let cfg = json!( { "server" : { "port" : 9000, "hosts" : [ "a", "b" ] }, "debug" : true } );
+    assert_eq!(cfg.get_or("debug", &Json::Boolean(false)), &Json::Boolean(true));
+    assert_eq!(cfg.get_or("verbose", &Json::Boolean(false)), &Json::Boolean(false));
+    assert_eq!(cfg["server"]["hosts"].get_or("0", &Json::Null), &Json::Null);
+    assert_eq!(cfg.get_path_or("server.port", &DEFAULT_PORT), &json!( 9000 ));
+    assert_eq!(cfg.get_path_or("server.hosts.1", &DEFAULT_PORT), &json!( "b" ));
+    assert_eq!(cfg.get_path_or("server.timeout", &DEFAULT_PORT), &DEFAULT_PORT);
+    assert_eq!(cfg.get_path_or("server.hosts.x", &DEFAULT_PORT), &DEFAULT_PORT);
+    assert_eq!(cfg.get_path_or("debug.level", &DEFAULT_PORT), &DEFAULT_PORT);
+}
+
+#[test]
+fn test_flatten_round_trip() {
+    let doc = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    let flat = doc.flatten();
+    assert_eq!(flat.len(), 8);
+    assert_eq!(flat.get("dummy.overview"), Some(&Json::Boolean(true)));
+    assert_eq!(flat.get("elements.3"), Some(&Json::Integer(43)));
+    assert_eq!(flat.get("dummy"), None);
+    assert_eq!(Json::unflatten(flat), doc);
+}
+
+#[test]
+fn test_flatten_edge_cases() {
+    let doc = json!( { "empty" : [], "none" : {}, "list" : [ 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, [ "x" ] ] } );
+    let flat = doc.flatten();
+    assert_eq!(flat.get("empty"), Some(&json!( [] )));
+    assert_eq!(flat.get("list.10.0"), Some(&json!( "x" )));
+    assert_eq!(Json::unflatten(flat), doc);
+    assert_eq!(json!( "scalar" ).flatten(), HashMap::from([ (String::new(), json!( "scalar" )) ]));
+
+    let mut flat = HashMap::new();
+    flat.insert(String::from("a.2"), json!( true ));
+    flat.insert(String::from("b"), json!( 1 ));
+    flat.insert(String::from("b.c"), json!( 2 ));
+    assert_eq!(Json::unflatten(flat), json!( { "a" : [ null, null, true ], "b" : { "c" : 2 } } ));
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_preserve_order() {
+    let res = json!( { "width" : 1, "height" : 2, "elements" : 3, "dummy" : 4 } );
+    let keys: Vec<&str> = res.as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys, [ "width", "height", "elements", "dummy" ]);
+    assert_eq!(res.to_string(), r#"{"width":1,"height":2,"elements":3,"dummy":4}"#);
+
+    let parsed = Json::from_str(r#"{"z":1,"a":2,"m":3}"#).unwrap();
+    assert_eq!(parsed.to_string(), r#"{"z":1,"a":2,"m":3}"#);
+    assert_eq!(parsed.get("a"), Some(&Json::Integer(2)));
+}
+
+#[test]
+fn test_integer_variant() {
+    assert_eq!(json!( 9007199254740993u64 ), Json::Integer(9007199254740993));
+    assert_eq!(Json::from(-7i8), Json::Integer(-7));
+    assert_eq!(json!( 2.0 ), Json::Number(2.0));
+    assert_eq!(Json::from(u64::MAX), Json::Number(u64::MAX as f64));
+    assert_eq!(Json::from(i64::MIN), Json::Integer(i64::MIN));
+}
+
+#[test]
+fn test_integer_round_trip() {
+    let ts = -1700000000123i64;
+    let value = json!( { "id" : 9007199254740993u64, "ts" : ts } );
+    assert_eq!(json!( 9007199254740993u64 ).to_string(), "9007199254740993");
+    assert_eq!(Json::from_str(&value.to_string()), Ok(value));
+    assert_eq!(Json::from_str("9223372036854775808"), Ok(Json::Number(9223372036854775808.0)));
+    assert_eq!(Json::from_str("-0"), Ok(Json::Integer(0)));
+    assert_eq!(Json::from_str("1e2"), Ok(Json::Number(100.0)));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_round_trip() {
+    let value = json!( { "null" : null, "bool" : true, "int" : 9007199254740993u64, "float" : 2.5, "string" : "a\"b", "array" : [ 1, [] ], "object" : { "x" : {} } } );
+    let text = serde_json::to_string(&value).unwrap();
+    assert_eq!(serde_json::from_str::<Json>(&text).unwrap(), value);
+    assert_eq!(Json::from_str(&text), Ok(value));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_data_model() {
+    assert_eq!(serde_json::to_string(&json!( [ null, false, 1, 1.5, "s" ] )).unwrap(), r#"[null,false,1,1.5,"s"]"#);
+    assert_eq!(serde_json::from_str::<Json>("18446744073709551615").unwrap(), Json::Number(u64::MAX as f64));
+    assert_eq!(serde_json::from_str::<Json>(r#"{"a":[1.0]}"#).unwrap(), json!( { "a" : [ 1.0 ] } ));
+}
+
+#[test]
+#[cfg(feature = "serde_json")]
+fn test_serde_json_value_round_trip() {
+    let value = json!( { "null" : null, "bool" : true, "int" : (-7), "float" : 2.5, "string" : "a\"b", "array" : [ 1, [] ], "object" : { "x" : {} } } );
+    let converted = serde_json::Value::from(value.clone());
+    assert_eq!(converted, serde_json::json!({ "null": null, "bool": true, "int": -7, "float": 2.5, "string": "a\"b", "array": [1, []], "object": { "x": {} } }));
+    assert_eq!(Json::from(converted), value);
+    let back = Json::from(serde_json::json!([1, 1.0, u64::MAX]));
+    assert!(matches!(back[0], Json::Integer(1)));
+    assert!(matches!(back[1], Json::Number(n) if n == 1.0));
+    assert!(matches!(back[2], Json::Number(n) if n == u64::MAX as f64));
+    assert_eq!(serde_json::Value::from(Json::Number(f64::NAN)), serde_json::Value::Null);
+    assert!(serde_json::Value::from(Json::Integer(3)).is_i64());
+}
+
+#[test]
+fn test_from_char_and_string_ref() {
+    assert_eq!(Json::from('x'), Json::String(String::from("x")));
+    assert_eq!(Json::from('😀'), json!( "😀" ));
+    let name = String::from("dummy");
+    let initial = 'd';
+    assert_eq!(Json::from(&name), json!( "dummy" ));
+    assert_eq!(json!( { "name" : (&name), "initial" : initial } ), json!( { "name" : "dummy", "initial" : "d" } ));
+    assert_eq!(name, "dummy");
+}
+
+#[test]
+fn test_from_vec() {
+    assert_eq!(Json::from(vec![ 1, 2, 3 ]), json!( [ 1, 2, 3 ] ));
+    assert_eq!(Json::from(vec![ vec![ true ], vec![] ]), json!( [ [ true ], [] ] ));
+    assert_eq!(Json::from(Vec::<Json>::new()), json!( [] ));
+}
+
+#[test]
+fn test_from_fixed_size_array() {
+    assert_eq!(Json::from([ true, false ]), Json::Array(vec![ Json::Boolean(true), Json::Boolean(false) ]));
+    assert_eq!(Json::from([ [ 1, 2 ], [ 3, 4 ] ]), json!( [ [ 1, 2 ], [ 3, 4 ] ] ));
+    assert_eq!(Json::from([ 0u8; 0 ]), json!( [] ));
+    let flags = [ true, false ];
+    assert_eq!(json!( { "flags" : flags } ), json!( { "flags" : [ true, false ] } ));
+}
+
+#[test]
+fn test_from_slice() {
+    let names = [ "a", "b" ];
+    assert_eq!(Json::from(&names[..]), json!( [ "a", "b" ] ));
+    let elements = vec![ json!( null ), json!( 1.5 ) ];
+    assert_eq!(Json::from(elements.as_slice()), json!( [ null, 1.5 ] ));
+}
+
+#[test]
+fn test_from_hash_map() {
+    let mut sizes: HashMap<String, i32> = HashMap::new();
+    sizes.insert("width".to_string(), 100);
+    sizes.insert("height".to_string(), 480);
+    let res = Json::from(sizes);
+    assert_eq!(res.as_object().map(|o| o.len()), Some(2));
+    assert_eq!(res.get("width"), Some(&Json::Integer(100)));
+    assert_eq!(res.get("height"), Some(&Json::Integer(480)));
+    assert_eq!(res.get("depth"), None);
+}
+
+#[test]
+fn test_from_btree_map() {
+    let mut flags = BTreeMap::new();
+    flags.insert("overview".to_string(), vec![ true ]);
+    assert_eq!(Json::from(flags), json!( { "overview" : [ true ] } ));
+}
+
+#[test]
+fn test_from_option() {
+    let name: Option<String> = Some("moro".to_string());
+    let missing: Option<String> = None;
+    assert_eq!(Json::from(name), json!( "moro" ));
+    assert_eq!(Json::from(missing), json!( null ));
+    assert_eq!(Json::from(Some(vec![ Some(1), None ])), json!( [ 1, null ] ));
+}
+
+#[test]
+fn test_from_option_in_macro() {
+    let width: Option<u32> = Some(100);
+    let height: Option<f64> = None;
+    assert_eq!(json!( { "width" : width, "height" : height } ), json!( { "width" : 100, "height" : null } ));
+}
+
+#[test]
+fn test_trailing_comma_array() {
+    assert_eq!(json!( [ 1, 2, 3, ] ), json!( [ 1, 2, 3 ] ));
+    assert_eq!(json!( [ [ true, ], ] ), json!( [ [ true ] ] ));
+    assert_eq!(json!( [] ), Json::Array(vec![]));
+}
+
+#[test]
+fn test_trailing_comma_object() {
+    assert_eq!(json!( { "a" : 1, "b" : 2, } ), json!( { "a" : 1, "b" : 2 } ));
+    assert_eq!(json!( { "a" : { "b" : [ 1, ], }, } ), json!( { "a" : { "b" : [ 1 ] } } ));
+    assert_eq!(json!( {} ), Json::Object(Map::default()));
+}
+
+#[test]
+fn test_expression_keys() {
+    let key = String::from("width");
+    let prefix = "dummy";
+    let res = json!( { (key) : 100, (format!("{}_{}", prefix, 2)) : true, ("lit") : null } );
+    assert_eq!(res, json!( { "width" : 100, "dummy_2" : true, "lit" : null } ));
+    assert_eq!(key, "width");
+}
+
+#[test]
+fn test_expression_keys_mixed_with_literals() {
+    let index = 3;
+    let res = json!( { "elements" : [ 1 ], (index) : { (index + 1) : "four" } } );
+    assert_eq!(res["3"]["4"], json!( "four" ));
+    assert_eq!(res["elements"], json!( [ 1 ] ));
+}
+
+#[test]
+fn test_splice_existing_json() {
+    let dummy = json!( { "overview" : true } );
+    let elements = json!( [ 1, 2, 3 ] );
+    let shared = &elements;
+    let res = json!( { "dummy" : dummy, "elements" : shared, "again" : [ shared ] } );
+    assert_eq!(res["dummy"]["overview"], json!( true ));
+    assert_eq!(res["elements"], elements);
+    assert_eq!(res["again"][0], elements);
+}
+
+#[test]
+fn test_try_from_json() {
+    assert_eq!(f64::try_from(json!( 3.0 )), Ok(3.0));
+    assert_eq!(f64::try_from(json!( 3 )), Ok(3.0));
+    assert_eq!(bool::try_from(json!( true )), Ok(true));
+    assert_eq!(String::try_from(json!( "yes" )), Ok("yes".to_string()));
+}
+
+#[test]
+fn test_try_from_json_mismatch() {
+    let err = f64::try_from(json!( "x" )).unwrap_err();
+    assert_eq!(err, ConversionError { expected: "number", found: "string" });
+    assert_eq!(err.to_string(), "expected JSON number, found string");
+    assert!(bool::try_from(json!( null )).is_err());
+    assert!(String::try_from(json!( [ "yes" ] )).is_err());
+}
+
+#[test]
+fn test_hash_set_dedup() {
+    use std::collections::HashSet;
+    let mut set = HashSet::new();
+    assert!(set.insert(json!( { "width" : 100, "elements" : [ 1, 2.5 ], "dummy" : { "overview" : true } } )));
+    assert!(!set.insert(Json::from_str(r#"{"dummy":{"overview":true},"elements":[1.0,2.5],"width":100.0}"#).unwrap()));
+    assert!(set.insert(json!( { "width" : 101 } )));
+    assert!(set.insert(json!( 0 )));
+    assert!(!set.insert(Json::from(-0.0)));
+    assert!(!set.insert(json!( 0.0 )));
+    assert!(set.insert(json!( "0" )));
+    assert!(set.insert(json!( [ 0 ] )));
+    assert!(set.insert(json!( 1e300 )));
+    assert!(!set.insert(Json::from_str("1e300").unwrap()));
+    assert!(set.insert(Json::from(1u64 << 63)));
+    assert!(set.insert(Json::from(f64::NAN)));
+    assert!(!set.insert(Json::from(-f64::NAN)));
+    assert!(set.contains(&json!( [ (f64::NAN) ] )[0]));
+    assert_eq!(set.len(), 8);
+}
+
+#[test]
+fn test_eq_unordered() {
+    assert!(json!( [ 1, 2, 3 ] ).eq_unordered(&json!( [ 3, 1, 2 ] )));
+    assert!(!json!( [ 1, 2, 3 ] ).eq_unordered(&json!( [ 1, 2, 2 ] )));
+    assert!(!json!( [ 1, 2, 2 ] ).eq_unordered(&json!( [ 1, 1, 2 ] )));
+    assert!(!json!( [ 1, 2 ] ).eq_unordered(&json!( [ 1, 2, 2 ] )));
+    let a = json!( { "tags" : [ "x", "y" ], "rows" : [ { "ids" : [ 1, 2 ] }, { "ids" : [] } ] } );
+    let b = json!( { "rows" : [ { "ids" : [] }, { "ids" : [ 2, 1.0 ] } ], "tags" : [ "y", "x" ] } );
+    assert_ne!(a, b);
+    assert!(a.eq_unordered(&b));
+    assert!(!a.eq_unordered(&json!( { "tags" : [ "y", "x" ] } )));
+    assert!(!json!( { "a" : [ 1 ] } ).eq_unordered(&json!( { "b" : [ 1 ] } )));
+}
+
+#[test]
+fn test_eq_ignoring_null() {
+    let a = json!( { "name" : "x", "dummy" : { "overview" : true, "depth" : null }, "rows" : [ { "id" : 1, "tag" : null } ] } );
+    let b = json!( { "name" : "x", "dummy" : { "overview" : true }, "rows" : [ { "id" : 1.0 } ], "extra" : null } );
+    assert_ne!(a, b);
+    assert!(a.eq_ignoring_null(&b));
+    assert!(b.eq_ignoring_null(&a));
+
+    assert!(!json!( { "a" : null } ).eq_ignoring_null(&json!( { "a" : false } )));
+    assert!(!json!( { "a" : { "b" : null, "c" : 1 } } ).eq_ignoring_null(&json!( { "a" : {} } )));
+    assert!(!json!( [ null ] ).eq_ignoring_null(&json!( [] )));
+    assert!(!json!( null ).eq_ignoring_null(&json!( {} )));
+}
+
+#[test]
+fn test_merge_nested_objects() {
+    let mut base = json!( { "width" : 100, "dummy" : { "overview" : true, "depth" : { "a" : 1 } }, "elements" : [ 1, 2 ] } );
+    base.merge(json!( { "height" : 480, "dummy" : { "overview" : false, "depth" : { "b" : 2 } }, "elements" : [ 3 ] } ));
+    assert_eq!(base, json!( {
+        "width" : 100,
+        "height" : 480,
+        "dummy" : { "overview" : false, "depth" : { "a" : 1, "b" : 2 } },
+        "elements" : [ 3 ]
+    } ));
+}
+
+#[test]
+fn test_merge_scalar_override() {
+    let mut base = json!( { "dummy" : { "overview" : true } } );
+    base.merge(json!( { "dummy" : "flat" } ));
+    assert_eq!(base, json!( { "dummy" : "flat" } ));
+
+    let mut scalar = json!( 1 );
+    scalar.merge(json!( { "a" : 1 } ));
+    assert_eq!(scalar, json!( { "a" : 1 } ));
+
+    let mut object = json!( { "a" : 1 } );
+    object.merge(json!( null ));
+    assert_eq!(object, json!( null ));
+}
+
+#[test]
+fn test_merge_array_strategies() {
+    let base = json!( { "ports" : [ 80, { "tls" : false, "host" : "a" } ], "tags" : [ 1, 2, 3 ] } );
+    let overlay = json!( { "ports" : [ 8080, { "tls" : true }, 9090 ], "tags" : [ 4 ] } );
+    let merged = |arrays| {
+        let mut res = base.clone();
+        res.merge_with_options(overlay.clone(), MergeOptions { arrays });
+        res
+    };
+    assert_eq!(merged(ArrayMerge::Replace), overlay);
+    assert_eq!(merged(ArrayMerge::Concat), json!( {
+        "ports" : [ 80, { "tls" : false, "host" : "a" }, 8080, { "tls" : true }, 9090 ],
+        "tags" : [ 1, 2, 3, 4 ]
+    } ));
+    assert_eq!(merged(ArrayMerge::ByIndex), json!( {
+        "ports" : [ 8080, { "tls" : true, "host" : "a" }, 9090 ],
+        "tags" : [ 4, 2, 3 ]
+    } ));
+
+    let mut res = base.clone();
+    res.merge(overlay.clone());
+    assert_eq!(res, merged(ArrayMerge::default()));
+}
+
+#[test]
+fn test_merge_patch_rfc7386_examples() {
+    let examples = [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+        (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#)
+    ];
+    for (target, patch, expected) in examples {
+        let mut doc = Json::from_str(target).unwrap();
+        doc.apply_merge_patch(&Json::from_str(patch).unwrap());
+        assert_eq!(doc, Json::from_str(expected).unwrap(), "patching {} with {}", target, patch);
+    }
+}
+
+#[test]
+fn test_merge_patch_differs_from_merge() {
+    let patch = json!( { "dummy" : null } );
+    let mut merged = json!( { "dummy" : 1, "width" : 2 } );
+    let mut patched = merged.clone();
+    merged.merge(patch.clone());
+    patched.apply_merge_patch(&patch);
+    assert_eq!(merged, json!( { "dummy" : null, "width" : 2 } ));
+    assert_eq!(patched, json!( { "width" : 2 } ));
+}
+
+// Apply a patch given as JSON text to a document given as JSON text
+fn patched(doc: &str, patch: &str) -> Result<Json, PatchError> {
+    let mut doc = Json::from_str(doc).unwrap();
+    doc.apply_patch(&Json::from_str(patch).unwrap())?;
+    Ok(doc)
+}
+
+#[test]
+fn test_patch_rfc6902_examples() {
+    let cases = [
+        (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz","value":"qux"}]"#, r#"{"baz":"qux","foo":"bar"}"#),
+        (r#"{"foo":["bar","baz"]}"#, r#"[{"op":"add","path":"/foo/1","value":"qux"}]"#, r#"{"foo":["bar","qux","baz"]}"#),
+        (r#"{"baz":"qux","foo":"bar"}"#, r#"[{"op":"remove","path":"/baz"}]"#, r#"{"foo":"bar"}"#),
+        (r#"{"foo":["bar","qux","baz"]}"#, r#"[{"op":"remove","path":"/foo/1"}]"#, r#"{"foo":["bar","baz"]}"#),
+        (r#"{"baz":"qux","foo":"bar"}"#, r#"[{"op":"replace","path":"/baz","value":"boo"}]"#, r#"{"baz":"boo","foo":"bar"}"#),
+        (r#"{"foo":{"bar":"baz","waldo":"fred"},"qux":{"corge":"grault"}}"#,
+         r#"[{"op":"move","from":"/foo/waldo","path":"/qux/thud"}]"#,
+         r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#),
+        (r#"{"foo":["all","grass","cows","eat"]}"#, r#"[{"op":"move","from":"/foo/1","path":"/foo/3"}]"#, r#"{"foo":["all","cows","eat","grass"]}"#),
+        (r#"{"baz":"qux","foo":["a",2,"c"]}"#,
+         r#"[{"op":"test","path":"/baz","value":"qux"},{"op":"test","path":"/foo/1","value":2}]"#,
+         r#"{"baz":"qux","foo":["a",2,"c"]}"#),
+        (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/child","value":{"grandchild":{}}}]"#, r#"{"foo":"bar","child":{"grandchild":{}}}"#),
+        (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz","value":"qux","xyz":123}]"#, r#"{"foo":"bar","baz":"qux"}"#),
+        (r#"{"/":9,"~1":10}"#, r#"[{"op":"test","path":"/~01","value":10}]"#, r#"{"/":9,"~1":10}"#),
+        (r#"{"foo":["bar"]}"#, r#"[{"op":"add","path":"/foo/-","value":["abc","def"]}]"#, r#"{"foo":["bar",["abc","def"]]}"#),
+        (r#"{"foo":1}"#, r#"[{"op":"copy","from":"/foo","path":"/bar"},{"op":"replace","path":"","value":[]}]"#, r#"[]"#)
+    ];
+    for (doc, patch, expected) in cases {
+        assert_eq!(patched(doc, patch), Ok(Json::from_str(expected).unwrap()), "patch {}", patch);
+    }
+}
+
+#[test]
+fn test_patch_rfc6902_errors() {
+    assert_eq!(patched(r#"{"baz":"qux"}"#, r#"[{"op":"test","path":"/baz","value":"bar"}]"#), Err(PatchError::TestFailed(String::from("/baz"))));
+    assert_eq!(patched(r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz/bat","value":"qux"}]"#), Err(PatchError::PathNotFound(String::from("/baz/bat"))));
+    assert_eq!(patched(r#"{"/":9,"~1":10}"#, r#"[{"op":"test","path":"/~01","value":"10"}]"#), Err(PatchError::TestFailed(String::from("/~01"))));
+    assert_eq!(patched(r#"[1]"#, r#"[{"op":"add","path":"/2","value":0}]"#), Err(PatchError::PathNotFound(String::from("/2"))));
+    assert_eq!(patched(r#"{"a":{}}"#, r#"[{"op":"move","from":"/a","path":"/a/b"}]"#).unwrap_err().to_string(), "invalid JSON patch: operation 0 moves \"/a\" into itself");
+    assert!(matches!(patched(r#"{}"#, r#"[{"op":"frobnicate","path":""}]"#), Err(PatchError::InvalidPatch(_))));
+    assert!(matches!(patched(r#"{}"#, r#"[{"op":"add","path":"/a"}]"#), Err(PatchError::InvalidPatch(_))));
+    assert!(matches!(patched(r#"{}"#, r#"{"op":"add"}"#), Err(PatchError::InvalidPatch(_))));
+}
+
+#[test]
+fn test_patch_is_atomic() {
+    let mut doc = json!( { "a" : 1 } );
+    let patch = json!( [ { "op" : "remove", "path" : "/a" }, { "op" : "remove", "path" : "/missing" } ] );
+    assert_eq!(doc.apply_patch(&patch), Err(PatchError::PathNotFound(String::from("/missing"))));
+    assert_eq!(doc, json!( { "a" : 1 } ));
+}
+
+#[test]
+fn test_diff_objects() {
+    let old = json!( { "name" : "a", "gone" : 1, "nested" : { "x" : 1, "y" : true }, "a/b" : 0 } );
+    let new = json!( { "name" : "b", "added" : [ 1 ], "nested" : { "x" : 2, "y" : true }, "a/b" : 0 } );
+    assert_eq!(Json::diff(&old, &new), vec![
+        Change::Added { path: String::from("/added"), value: json!( [ 1 ] ) },
+        Change::Removed { path: String::from("/gone"), value: json!( 1 ) },
+        Change::Changed { path: String::from("/name"), old: json!( "a" ), new: json!( "b" ) },
+        Change::Changed { path: String::from("/nested/x"), old: json!( 1 ), new: json!( 2 ) }
+    ]);
+    assert_eq!(Json::diff(&old, &old), vec![]);
+}
+
+#[test]
+fn test_diff_arrays_and_types() {
+    let old = json!( { "list" : [ 1, 2, 3 ], "m~n" : { "k/1" : [] } } );
+    let new = json!( { "list" : [ 1, 5 ], "m~n" : { "k/1" : {} } } );
+    assert_eq!(Json::diff(&old, &new), vec![
+        Change::Changed { path: String::from("/list/1"), old: json!( 2 ), new: json!( 5 ) },
+        Change::Removed { path: String::from("/list/2"), value: json!( 3 ) },
+        Change::Changed { path: String::from("/m~0n/k~11"), old: json!( [] ), new: json!( {} ) }
+    ]);
+    assert_eq!(Json::diff(&json!( [ 1 ] ), &json!( [ 1, null ] )), vec![ Change::Added { path: String::from("/1"), value: Json::Null } ]);
+    assert_eq!(Json::diff(&json!( 1 ), &json!( "1" )), vec![ Change::Changed { path: String::new(), old: json!( 1 ), new: json!( "1" ) } ]);
+}
+
+#[test]
+fn test_len() {
+    assert_eq!(json!( [ 1, 2, 3, 43, 5 ] ).len(), Some(5));
+    assert_eq!(json!( { "test" : 1 } ).len(), Some(1));
+    assert_eq!(json!( [] ).len(), Some(0));
+    assert_eq!(json!( "abc" ).len(), None);
+    assert_eq!(json!( null ).len(), None);
+}
+
+#[test]
+fn test_is_empty() {
+    assert!(json!( [] ).is_empty());
+    assert!(json!( {} ).is_empty());
+    assert!(!json!( [ null ] ).is_empty());
+    assert!(!json!( "" ).is_empty());
+    assert!(!json!( null ).is_empty());
+}
+
+#[test]
+fn test_entries() {
+    let res = json!( { "width" : 100, "height" : 480 } );
+    let mut keys: Vec<&String> = res.entries().map(|(k, _)| k).collect();
+    keys.sort();
+    assert_eq!(keys, [ "height", "width" ]);
+    assert_eq!(res.entries().filter_map(|(_, v)| v.as_f64()).sum::<f64>(), 580.0);
+    assert_eq!(json!( [ 1 ] ).entries().count(), 0);
+}
+
+#[test]
+fn test_contains_key_keys_values() {
+    let desc = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    let dummy = &desc["dummy"];
+    assert!(dummy.contains_key("overview"));
+    assert!(!dummy.contains_key("width"));
+    assert_eq!(dummy.keys().collect::<Vec<_>>(), [ "overview" ]);
+    assert_eq!(dummy.values().collect::<Vec<_>>(), [ &Json::Boolean(true) ]);
+    let mut keys: Vec<&String> = desc.keys().collect();
+    keys.sort();
+    assert_eq!(keys, [ "dummy", "elements", "height", "width" ]);
+    assert_eq!(desc.values().count(), 4);
+
+    let elements = &desc["elements"];
+    assert!(!elements.contains_key("0"));
+    assert_eq!(elements.keys().count(), 0);
+    assert_eq!(elements.values().count(), 0);
+    assert!(!Json::Null.contains_key("overview"));
+}
+
+#[test]
+fn test_entries_mut() {
+    let mut res = json!( { "width" : 100, "height" : 480 } );
+    for (key, value) in res.entries_mut() {
+        *value = Json::from(key.len());
+    }
+    assert_eq!(res, json!( { "width" : 5, "height" : 6 } ));
+    assert_eq!(json!( null ).entries_mut().count(), 0);
+}
+
+#[test]
+fn test_iter() {
+    let res = json!( [ 1, 2, 3, 43, 5 ] );
+    assert_eq!(res.iter().filter_map(Json::as_f64).sum::<f64>(), 54.0);
+    assert_eq!(json!( { "a" : 1 } ).iter().count(), 0);
+    assert_eq!(json!( "abc" ).iter().count(), 0);
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut res = json!( [ 1, "two", 3 ] );
+    for element in res.iter_mut().filter(|e| e.is_number()) {
+        *element = json!( null );
+    }
+    assert_eq!(res, json!( [ null, "two", null ] ));
+    assert_eq!(json!( {} ).iter_mut().count(), 0);
+}
+
+#[test]
+fn test_default_is_null() {
+    let x: Json = Default::default();
+    assert_eq!(x, Json::Null);
+
+    #[derive(Default)]
+    struct Holder {
+        value: Json
+    }
+    assert!(Holder::default().value.is_null());
+
+    let mut res = json!( [ 1 ] );
+    assert_eq!(std::mem::take(&mut res), json!( [ 1 ] ));
+    assert_eq!(res, Json::Null);
+}
+
+#[test]
+fn test_take() {
+    let mut res = json!( { "width" : 100, "elements" : [ 1, 2, 3, 43, 5 ] } );
+    let elements = res["elements"].take();
+    assert_eq!(elements, json!( [ 1, 2, 3, 43, 5 ] ));
+    assert_eq!(res, json!( { "width" : 100, "elements" : null } ));
+    assert_eq!(res["elements"].take(), Json::Null);
+}
+
+#[test]
+fn test_insert() {
+    let mut res = json!( { "width" : 100 } );
+    assert_eq!(res.insert("width", 200), Some(json!( 100 )));
+    assert_eq!(res.insert(String::from("height"), 480.0), None);
+    assert_eq!(res, json!( { "width" : 200, "height" : 480.0 } ));
+}
+
+#[test]
+fn test_insert_upgrades_null_and_ignores_scalars() {
+    let mut res = Json::Null;
+    res.insert("overview", true);
+    assert_eq!(res, json!( { "overview" : true } ));
+
+    let mut array = json!( [ 1 ] );
+    assert_eq!(array.insert("key", 1), None);
+    assert_eq!(array, json!( [ 1 ] ));
+}
+
+#[test]
+fn test_push_pop() {
+    let mut res = json!( [] );
+    res.push(1);
+    res.push("two");
+    res.push(json!( { "three" : 3 } ));
+    assert_eq!(res, json!( [ 1, "two", { "three" : 3 } ] ));
+    assert_eq!(res.pop(), Some(json!( { "three" : 3 } )));
+    assert_eq!(res.pop(), Some(json!( "two" )));
+    assert_eq!(res.pop(), Some(json!( 1 )));
+    assert_eq!(res.pop(), None);
+    assert_eq!(res, json!( [] ));
+
+    let mut null = Json::Null;
+    null.push(true);
+    assert_eq!(null, json!( [ true ] ));
+    let mut object = json!( { "a" : 1 } );
+    object.push(2);
+    assert_eq!(object, json!( { "a" : 1 } ));
+    assert_eq!(object.pop(), None);
+}
+
+#[test]
+fn test_remove() {
+    let mut res = json!( { "width" : 100, "height" : 480 } );
+    assert_eq!(res.remove("width"), Some(json!( 100 )));
+    assert_eq!(res.remove("width"), None);
+    assert_eq!(res, json!( { "height" : 480 } ));
+    assert_eq!(json!( "width" ).remove("width"), None);
+}
+
+#[test]
+fn test_entry() {
+    let mut res = json!( { "count" : 1 } );
+    for key in [ "count", "other" ] {
+        res.entry(key).and_modify(|v| *v = Json::from(v.as_f64().unwrap_or(0.0) as i64 + 1)).or_insert(json!( 0 ));
+    }
+    assert_eq!(res, json!( { "count" : 2, "other" : 0 } ));
+    *res.entry("list").or_insert_with(|| json!( [] )) = json!( [ 1 ] );
+    assert_eq!(res["list"], json!( [ 1 ] ));
+    assert_eq!(res.entry(String::from("list")).or_insert_with(|| unreachable!()), &json!( [ 1 ] ));
+    assert_eq!(res.entry("count").key(), "count");
+
+    let mut empty = Json::Null;
+    empty.entry("overview").or_insert(true);
+    assert_eq!(empty, json!( { "overview" : true } ));
+}
+
+#[test]
+#[should_panic]
+fn test_entry_non_object_panics() {
+    json!( [ 1 ] ).entry("key");
+}
+
+#[test]
+fn test_json_builder() {
+    let build = |overview: bool| JsonBuilder::new()
+        .set("width", 100)
+        .set("height", 480.0)
+        .set(String::from("elements"), vec![ 1, 2, 3 ])
+        .set_if(overview, "dummy", json!( { "overview" : true } ))
+        .build();
+    assert_eq!(build(true), json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3 ], "dummy" : { "overview" : true } } ));
+    assert_eq!(build(false), json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3 ] } ));
+
+    assert_eq!(JsonBuilder::new().build(), json!( {} ));
+    assert_eq!(JsonBuilder::new().set("a", 1).set("a", "two").build(), json!( { "a" : "two" } ));
+}
+
+#[test]
+fn test_retain() {
+    let mut res = json!( [ 1, 2, 3, 43, 5, 6.0 ] );
+    res.retain(|v| v.as_i64().is_some_and(|i| i % 2 == 0));
+    assert_eq!(res, json!( [ 2, 6 ] ));
+
+    let mut object = json!( { "a" : null, "b" : 1, "c" : null, "d" : { "e" : null } } );
+    object.retain_keys(|_, v| !v.is_null());
+    assert_eq!(object, json!( { "b" : 1, "d" : { "e" : null } } ));
+    object.retain_keys(|key, _| key != "b");
+    assert_eq!(object, json!( { "d" : { "e" : null } } ));
+
+    let mut scalar = json!( "x" );
+    scalar.retain(|_| false);
+    scalar.retain_keys(|_, _| false);
+    assert_eq!(scalar, json!( "x" ));
+    let mut array = json!( [ null ] );
+    array.retain_keys(|_, _| false);
+    assert_eq!(array, json!( [ null ] ));
+}
+
+#[test]
+fn test_prune_nulls() {
+    let mut res = json!( { "a" : null, "b" : { "c" : null, "d" : 1, "e" : { "f" : null } }, "g" : [ null, { "h" : null, "i" : 2 } ] } );
+    res.prune_nulls();
+    assert_eq!(res, json!( { "b" : { "d" : 1, "e" : {} }, "g" : [ null, { "i" : 2 } ] } ));
+    let mut null = Json::Null;
+    null.prune_nulls();
+    assert_eq!(null, Json::Null);
+}
+
+#[test]
+fn test_without_nulls() {
+    let original = json!( { "a" : null, "b" : { "c" : null, "d" : 1 }, "g" : [ null, { "h" : null } ] } );
+    let copy = original.without_nulls();
+    assert_eq!(copy, json!( { "b" : { "d" : 1 }, "g" : [ null, {} ] } ));
+    assert_eq!(original, json!( { "a" : null, "b" : { "c" : null, "d" : 1 }, "g" : [ null, { "h" : null } ] } ));
+    let mut pruned = original.clone();
+    pruned.prune_nulls();
+    assert_eq!(pruned, copy);
+}
+
+#[test]
+fn test_collect_array() {
+    assert_eq!((0..3).map(Json::from).collect::<Json>(), json!( [ 0, 1, 2 ] ));
+    assert_eq!([ "a", "b" ].into_iter().collect::<Json>(), json!( [ "a", "b" ] ));
+    assert_eq!(std::iter::empty::<bool>().collect::<Json>(), json!( [] ));
+}
+
+#[test]
+fn test_collect_object() {
+    let res: Json = [ ("width", 100), ("height", 480) ].into_iter().collect();
+    assert_eq!(res, json!( { "width" : 100, "height" : 480 } ));
+    let squares: Json = (1..=3).map(|i| (i.to_string(), i * i)).collect();
+    assert_eq!(squares, json!( { "1" : 1, "2" : 4, "3" : 9 } ));
+}
+
+#[test]
+fn test_into_iter_owned() {
+    let mut seen = Vec::new();
+    for element in json!( [ 1, "two", null ] ) {
+        seen.push(element);
+    }
+    assert_eq!(seen, vec![ json!( 1 ), json!( "two" ), json!( null ) ]);
+    assert_eq!(json!( { "a" : 1 } ).into_iter().count(), 0);
+    assert_eq!(json!( 5 ).into_iter().count(), 0);
+}
+
+#[test]
+fn test_into_iter_borrowed() {
+    let res = json!( [ 1, 2, 3, 43, 5 ] );
+    let mut total = 0.0;
+    for element in &res {
+        total += element.as_f64().unwrap();
+    }
+    assert_eq!(total, 54.0);
+    assert_eq!((&json!( "abc" )).into_iter().count(), 0);
+}
+
+#[test]
+fn test_to_writer() {
+    let value = json!( { "elements" : [ 1, 2.5, "x\n" ], "dummy" : { "overview" : true } } );
+    let mut out: Vec<u8> = Vec::new();
+    value.to_writer(&mut out).unwrap();
+    assert_eq!(out, value.to_string().into_bytes());
+}
+
+#[test]
+fn test_to_writer_pretty() {
+    let value = json!( { "elements" : [ 1, [] ] } );
+    let mut out: Vec<u8> = Vec::new();
+    value.to_writer_pretty(&mut out, 2).unwrap();
+    assert_eq!(out, b"{\n  \"elements\": [\n    1,\n    []\n  ]\n}");
+}
+
+#[test]
+fn test_to_writer_reports_io_error() {
+    let mut buffer = [0u8; 4];
+    let mut out = &mut buffer[..];
+    let err = json!( [ "too long for the buffer" ] ).to_writer(&mut out).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn test_ordering_across_types() {
+    let mut values = vec![ json!( {} ), json!( [] ), json!( "a" ), json!( 1 ), json!( true ), json!( null ) ];
+    values.sort();
+    assert_eq!(values, vec![ json!( null ), json!( true ), json!( 1 ), json!( "a" ), json!( [] ), json!( {} ) ]);
+    assert_eq!(values.binary_search(&json!( "a" )), Ok(3));
+    assert_eq!(values.binary_search(&json!( "b" )), Err(4));
+    assert!(json!( 1000 ) < json!( "0" ));
+    assert!(json!( false ) < json!( true ));
+}
+
+#[test]
+fn test_numeric_equality() {
+    assert_eq!(json!( 1 ), json!( 1.0 ));
+    assert_eq!(json!( 1.0 ), json!( 1 ));
+    assert_ne!(json!( 1 ), json!( 1.5 ));
+    assert_ne!(json!( 1 ), json!( "1" ));
+    assert_eq!(json!( [ 0, { "a" : 2 } ] ), json!( [ 0.0, { "a" : 2.0 } ] ));
+    assert_eq!(Json::Integer(1 << 60), Json::Number((1u64 << 60) as f64));
+    assert_ne!(Json::Integer((1 << 60) + 1), Json::Number((1u64 << 60) as f64));
+    assert_ne!(Json::Integer(i64::MAX), Json::Number(9223372036854775807.0));
+    assert_eq!(Json::Integer(i64::MIN), Json::Number(-9223372036854775808.0));
+    assert_eq!(Json::Number(f64::NAN), Json::Number(f64::NAN));
+    assert_eq!(Json::Number(f64::NAN), Json::Number(-f64::NAN));
+    assert_ne!(Json::Number(f64::NAN), Json::Number(f64::INFINITY));
+    assert_ne!(Json::Integer(0), Json::Number(f64::NAN));
+}
+
+#[test]
+fn test_ordering_within_types() {
+    assert!(json!( 2 ) < json!( 10 ));
+    assert!(json!( 2.5 ) < json!( 3 ));
+    assert!(Json::from(-3) < Json::from(-2.5));
+    assert!(Json::Integer(i64::MAX) < Json::Number(1e19));
+    assert_eq!(Json::Integer(1).partial_cmp(&Json::Number(1.0)), Some(Ordering::Equal));
+    assert_eq!(Json::Integer(1).partial_cmp(&Json::Integer(1)), Some(Ordering::Equal));
+    assert_eq!(json!( 1 ).cmp(&json!( 1.0 )), Ordering::Equal);
+    assert_eq!(Json::from(-0.0).cmp(&json!( 0 )), Ordering::Equal);
+    assert!(json!( "abc" ) < json!( "abd" ));
+    assert!(json!( [ 1, 2 ] ) < json!( [ 1, 3 ] ));
+    assert!(json!( [ 1 ] ) < json!( [ 1, 0 ] ));
+    assert!(json!( { "a" : 1, "b" : 2 } ) < json!( { "b" : 1, "a" : 2 } ));
+    assert_eq!(json!( { "a" : 1, "b" : 2 } ).partial_cmp(&json!( { "b" : 2, "a" : 1 } )), Some(Ordering::Equal));
+
+    let sorted = [ json!( 1 ), json!( 4 ), json!( 9 ) ];
+    assert_eq!(sorted.binary_search(&json!( 4 )), Ok(1));
+    assert_eq!(sorted.binary_search(&json!( 4.0 )), Ok(1));
+    assert_eq!(sorted.binary_search(&json!( 5 )), Err(2));
+}
+
+#[test]
+fn test_ordering_nan() {
+    let nan = Json::from(f64::NAN);
+    let mut values = [ nan.clone(), json!( "a" ), Json::from(f64::INFINITY), json!( 3 ), Json::from(-f64::NAN), Json::from(-1.5), json!( null ) ];
+    values.sort();
+    assert_eq!(values[..4], [ json!( null ), Json::from(-1.5), json!( 3 ), Json::from(f64::INFINITY) ]);
+    assert!(values[4..6].iter().all(|v| v.as_f64().is_some_and(f64::is_nan)));
+    assert_eq!(values[6], json!( "a" ));
+    assert_eq!(nan.cmp(&Json::Integer(i64::MAX)), Ordering::Greater);
+    assert_eq!(Json::Integer(i64::MIN).cmp(&nan), Ordering::Less);
+    assert_eq!(nan.cmp(&Json::from(-f64::NAN)), Ordering::Equal);
+    assert_eq!(nan.partial_cmp(&json!( 1 )), Some(Ordering::Greater));
+    assert!(nan < json!( "" ));
+}
+
+#[test]
+fn test_walk_order() {
+    let value = json!( [ 1, [ 2, [ 3 ] ], 4, { "five" : 5 } ] );
+    let mut seen = Vec::new();
+    value.walk(&mut |node| seen.push(node.to_string()));
+    assert_eq!(seen, [ "[1,[2,[3]],4,{\"five\":5}]", "1", "[2,[3]]", "2", "[3]", "3", "4", "{\"five\":5}", "5" ]);
+
+    let mut strings = 0;
+    json!( { "a" : "x", "b" : [ "y", 1, { "c" : "z" } ] } ).walk(&mut |node| if node.is_string() { strings += 1 });
+    assert_eq!(strings, 3);
+}
+
+#[test]
+fn test_walk_mut_redacts() {
+    let mut value = json!( { "user" : { "password" : "secret", "name" : "a" }, "tokens" : [ { "password" : [ "x" ] } ] } );
+    value.walk_mut(&mut |node| {
+        if let Some(password) = node.get_mut("password") {
+            *password = Json::from("***");
+        }
+    });
+    assert_eq!(value, json!( { "user" : { "password" : "***", "name" : "a" }, "tokens" : [ { "password" : "***" } ] } ));
+}
+
+#[test]
+fn test_number_precision() {
+    let res = json!( [ 1.23456789, 1234.5, 0.000123456, 2.0, 7, 123456789, 1e300 ] );
+    let with = |number_precision, indent| res.to_string_with_options(SerializeOptions { indent, number_precision, ..SerializeOptions::default() });
+    assert_eq!(with(None, None), res.to_string());
+    assert_eq!(with(Some(3), None), "[1.23,1230,0.000123,2,7,123456789,1e300]");
+    assert_eq!(with(Some(1), None), "[1,1000,0.0001,2,7,123456789,1e300]");
+    assert_eq!(with(Some(0), None), with(Some(1), None));
+    assert_eq!(with(Some(20), None), res.to_string());
+    assert_eq!(with(Some(2), Some(2)), "[\n  1.2,\n  1200,\n  0.00012,\n  2,\n  7,\n  123456789,\n  1e300\n]");
+    assert_eq!(with(None, Some(4)), res.to_pretty_string(4));
+    assert_eq!(Json::from(f64::NAN).to_string_with_options(SerializeOptions { number_precision: Some(2), ..SerializeOptions::default() }), "null");
+    assert_eq!(Json::from(1.23456e-300).to_string_with_options(SerializeOptions { number_precision: Some(3), ..SerializeOptions::default() }), "1.23e-300");
+}
+
+#[test]
+fn test_escape_options() {
+    let res = json!( { "</script>" : "<script>alert('a & b')</script>" } );
+    let with = |escape_solidus, escape_html| res.to_string_with_options(SerializeOptions { escape_solidus, escape_html, ..SerializeOptions::default() });
+    assert_eq!(with(false, false), res.to_string());
+    assert_eq!(with(false, false), r#"{"</script>":"<script>alert('a & b')</script>"}"#);
+    assert_eq!(with(true, false), r#"{"<\/script>":"<script>alert('a & b')<\/script>"}"#);
+    assert_eq!(with(false, true), r#"{"\u003c/script\u003e":"\u003cscript\u003ealert('a \u0026 b')\u003c/script\u003e"}"#);
+    assert_eq!(with(true, true), r#"{"\u003c\/script\u003e":"\u003cscript\u003ealert('a \u0026 b')\u003c\/script\u003e"}"#);
+    for (escape_solidus, escape_html) in [ (true, false), (false, true), (true, true) ] {
+        assert_eq!(Json::from_str(&with(escape_solidus, escape_html)), Ok(res.clone()));
+    }
+    let pretty = json!( [ "a/b" ] ).to_string_with_options(SerializeOptions { indent: Some(2), escape_solidus: true, ..SerializeOptions::default() });
+    assert_eq!(pretty, "[\n  \"a\\/b\"\n]");
+}
+
+#[test]
+fn test_to_vec() {
+    let res = json!( { "width" : 100, "elements" : [ 1, "ü", null ], "dummy" : { "overview" : true } } );
+    assert_eq!(res.to_vec(), res.to_string().into_bytes());
+    assert_eq!(res.to_vec_pretty(4), res.to_pretty_string(4).into_bytes());
+    assert_eq!(Json::from_reader(&res.to_vec()[..]), Ok(res));
+}
+
+#[test]
+fn test_to_canonical_string() {
+    let mut first = json!( { "b" : [ 1, { "y" : 2, "x" : 1 } ], "a" : null } );
+    let mut second = Json::Null;
+    second.insert("a", Json::Null);
+    second.insert("b", json!( [ 1.0, { "x" : 1, "y" : 2 } ] ));
+    assert_eq!(first.to_canonical_string(), "{\"a\":null,\"b\":[1,{\"x\":1,\"y\":2}]}");
+    assert_eq!(first.to_canonical_string(), second.to_canonical_string());
+
+    // Rebuilding the map in another insertion order must not change anything
+    for key in [ "a", "b", "c" ] {
+        first.insert(key, Json::from(-0.0));
+    }
+    first.remove("b");
+    assert_eq!(first.to_canonical_string(), "{\"a\":0,\"c\":0}");
+}
+
+#[test]
+fn test_canonical_numbers_match_across_variants() {
+    for i in [ 0, 7, -42, 1_000_000, 1 << 53, 1_000_000_000_000_000_000, -(1 << 62), i64::MIN ] {
+        let integer = Json::Integer(i);
+        let number = Json::Number(i as f64);
+        assert_eq!(integer, number);
+        assert_eq!(integer.to_canonical_string(), number.to_canonical_string(), "{}", i);
+    }
+    assert_eq!(Json::Number(1e18).to_canonical_string(), "1000000000000000000");
+    assert_eq!(Json::Integer(1_000_000_000_000_000_000).to_canonical_string(), "1000000000000000000");
+    assert_eq!(Json::from_str("-0.0").unwrap().to_canonical_string(), "0");
+    assert_eq!(json!( [ 0.5, 1e300, -2.5e-7, 9223372036854775808.0 ] ).to_canonical_string(), "[5e-1,1e300,-2.5e-7,9.223372036854776e18]");
+    assert_eq!(Json::from(f64::NAN).to_canonical_string(), "null");
+    for text in [ "[5e-1,1e300,-2.5e-7]", "1000000000000000000" ] {
+        let value = Json::from_str(text).unwrap();
+        assert_eq!(Json::from_str(&value.to_canonical_string()), Ok(value));
+    }
+}
+
+#[test]
+fn test_ndjson_round_trip() {
+    let records = vec![ json!( { "level" : "info", "msg" : "a\nb" } ), json!( [ 1, 2 ] ), json!( null ) ];
+    let text = Json::to_ndjson(&records);
+    assert_eq!(text.lines().count(), 3);
+    assert!(text.ends_with("\n"));
+    assert_eq!(Json::from_ndjson(&text), Ok(records));
+    assert_eq!(Json::to_ndjson(&[]), "");
+}
+
+#[test]
+fn test_from_ndjson_skips_blank_lines_and_reports_line() {
+    assert_eq!(Json::from_ndjson("1\n\n  \r\n\"x\"\r\n"), Ok(vec![ json!( 1 ), json!( "x" ) ]));
+    assert_eq!(Json::from_ndjson("{}\n\n[1,]\n"), Err(ParseError { line: 3, column: 4, kind: ParseErrorKind::UnexpectedChar(']') }));
+}
+
+#[test]
+fn test_events() {
+    let input = r#"{ "width" : 100, "elements" : [ 1, "x", [] ], "dummy" : { "overview" : true } }"#;
+    let events: Result<Vec<Event>, ParseError> = Json::events(input.as_bytes()).collect();
+    assert_eq!(events, Ok(vec![
+        Event::StartObject,
+        Event::Key(String::from("width")), Event::Value(json!( 100 )),
+        Event::Key(String::from("elements")), Event::StartArray,
+        Event::Value(json!( 1 )), Event::Value(json!( "x" )), Event::StartArray, Event::EndArray,
+        Event::EndArray,
+        Event::Key(String::from("dummy")), Event::StartObject,
+        Event::Key(String::from("overview")), Event::Value(json!( true )),
+        Event::EndObject,
+        Event::EndObject
+    ]));
+    let scalar: Vec<_> = Json::events(&b" \"a\\u00e9\\ud83d\\ude00\" "[..]).collect();
+    assert_eq!(scalar, [ Ok(Event::Value(json!( "aé😀" ))) ]);
+}
+
+#[test]
+fn test_events_report_errors_like_from_str() {
+    for input in [ "[1, 2", "[1, 2,]", "{\"a\" 1}", "{\"a\":1,}", "nul", "[tru]", "\"tab\there\"", "\"\\x\"", "[\"\\ud800\"]", "1 2", "", "{ 1 : 2 }", "[1.5e]", "-", "[01]" ] {
+        let last = Json::events(input.as_bytes()).last().unwrap();
+        assert_eq!(last, Err(Json::from_str(input).unwrap_err()), "{}", input);
+    }
+    let mut events = Json::events(&b"[ 1, \n ]"[..]);
+    assert_eq!(events.next(), Some(Ok(Event::StartArray)));
+    assert_eq!(events.next(), Some(Ok(Event::Value(json!( 1 )))));
+    assert_eq!(events.next(), Some(Err(ParseError { line: 2, column: 2, kind: ParseErrorKind::UnexpectedChar(']') })));
+    assert_eq!(events.next(), None);
+    assert_eq!(Json::events(&b"[\"\xff\"]"[..]).last(), Some(Err(ParseError { line: 1, column: 3, kind: ParseErrorKind::InvalidUtf8(2) })));
+    for input in [ &b"\xef\xbb\xbf[\n\"caf\xc3\xa9\", \"\xe2\x82\""[..], b"[\"\xc3\"]", b"\"\xe9t\xc3\xa9\"", b"\"\xf0\x9f\x98\x80\xbf\"" ] {
+        let last = Json::events(input).last().unwrap();
+        assert_eq!(last, Err(Json::from_slice(input).unwrap_err()), "{:?}", input);
+    }
+    let events: Result<Vec<Event>, ParseError> = Json::events(&b"\xef\xbb\xbf [\"\xc3\xa9\"]"[..]).collect();
+    assert_eq!(events, Ok(vec![ Event::StartArray, Event::Value(json!( "\u{e9}" )), Event::EndArray ]));
+}
+
+#[test]
+fn test_events_deep_nesting_uses_no_stack() {
+    let depth = 100_000;
+    let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let mut count = 0;
+    let options = ParseOptions { max_depth: usize::MAX, ..ParseOptions::default() };
+    for event in Json::events_with_options(input.as_bytes(), options) {
+        assert!(event.is_ok());
+        count += 1;
+    }
+    assert_eq!(count, depth * 2);
+}
+
+#[test]
+fn test_events_honor_parse_options() {
+    let defaults = ParseOptions::default();
+    let extended = ParseOptions { allow_comments: true, allow_trailing_commas: true, allow_nan: true, lenient_numbers: true, ..defaults };
+    let limited = ParseOptions { max_depth: 2, max_items: 3, max_string_length: 3, duplicate_key: DuplicateKey::Error, ..defaults };
+    let inputs = [
+        "[ 1, 2, ]", "{ \"a\" : 1, }", "[ 1 // one\n, 2 /* two */ ]", "[ / ]", "/* open", "[ NaN, -Infinity, Infinity ]",
+        "[ +1, 007, .5, 1. ]", "1e400", "[ [ [] ] ]", "[ [], {} ]", "[ 1, 2, 3, 4 ]", "{ \"a\" : [ 1, 2 ] }",
+        "[ \"abc\", \"abcd\" ]", "\"\\u00e9\\u00e9\"", "{ \"a\" : 1, \"b\" : 2, \"a\" : 3 }", "{ \"a\" : { \"a\" : 1 } }"
+    ];
+    for options in [ defaults, extended, limited ] {
+        for input in inputs {
+            let events: Result<Vec<Event>, ParseError> = Json::events_with_options(input.as_bytes(), options).collect();
+            assert_eq!(events.err(), Json::from_str_with_options(input, options).err(), "{} {:?}", input, options);
+        }
+    }
+    let events: Result<Vec<Event>, ParseError> = Json::events_with_options(&b"[ NaN, 1e400 ]"[..], extended).collect();
+    assert_eq!(events.unwrap()[2], Event::Value(Json::from(f64::INFINITY)));
+}
+
+#[test]
+fn test_from_reader() {
+    let input: &[u8] = b"{ \"elements\" : [ 1, 2.5, \"x\" ] }";
+    let value = Json::from_reader(io::Cursor::new(input)).unwrap();
+    assert_eq!(value, json!( { "elements" : [ 1, 2.5, "x" ] } ));
+}
+
+#[test]
+fn test_from_file() {
+    let path = std::env::temp_dir().join(format!("json_moro_from_file_{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "width" : 100, "dummy" : { "overview" : true } }"#).unwrap();
+    assert_eq!(Json::from_file(&path).unwrap(), json!( { "width" : 100, "dummy" : { "overview" : true } } ));
+
+    std::fs::write(&path, "{ \"width\" : }").unwrap();
+    match Json::from_file(&path) {
+        Err(LoadError::Parse(e)) => assert_eq!(e, ParseError { line: 1, column: 13, kind: ParseErrorKind::UnexpectedChar('}') }),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+    std::fs::write(&path, b"\xef\xbb\xbf[\"\xff\"]").unwrap();
+    match Json::from_file(&path) {
+        Err(LoadError::Parse(e)) => assert_eq!(e, ParseError { line: 1, column: 3, kind: ParseErrorKind::InvalidUtf8(5) }),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let missing = Json::from_file(&path).unwrap_err();
+    assert!(matches!(&missing, LoadError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+    assert!(std::error::Error::source(&missing).is_some());
+    assert!(missing.to_string().starts_with("cannot read JSON file: "));
+    let directory = Json::from_file(std::env::temp_dir()).unwrap_err();
+    assert!(matches!(directory, LoadError::Io(_)), "{:?}", directory);
+}
+
+#[test]
+fn test_to_file() {
+    let path = std::env::temp_dir().join(format!("json_moro_to_file_{}.json", std::process::id()));
+    let res = json!( { "width" : 100, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    res.to_file(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), res.to_string());
+    assert_eq!(Json::from_file(&path).unwrap(), res);
+    res.to_file_pretty(&path, 2).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), res.to_pretty_string(2));
+    assert_eq!(Json::from_file(&path).unwrap(), res);
+    std::fs::remove_file(&path).unwrap();
+    assert!(res.to_file(path.join("not_a_directory")).is_err());
+}
+
+#[test]
+fn test_from_reader_reports_io_error() {
+    struct Failing;
+    impl io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "dropped"))
+        }
+    }
+    let err = Json::from_reader(Failing).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Io(io::ErrorKind::ConnectionReset));
+    assert_eq!((err.line, err.column), (0, 0));
+
+    let err = Json::from_reader(io::Cursor::new(&b"\"\xff\""[..])).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::InvalidUtf8(1));
+}
+
+#[test]
+fn test_byte_order_mark_is_skipped() {
+    let expected = json!( { "width" : 100, "elements" : [ 1, 2 ] } );
+    assert_eq!(Json::from_str("\u{feff}{ \"width\" : 100, \"elements\" : [ 1, 2 ] }"), Ok(expected.clone()));
+    assert_eq!(Json::from_reader(&b"\xef\xbb\xbf{ \"width\" : 100, \"elements\" : [ 1, 2 ] }"[..]), Ok(expected));
+    assert_eq!(Json::from_str("\u{feff}[1,]").unwrap_err(), ParseError { line: 1, column: 4, kind: ParseErrorKind::UnexpectedChar(']') });
+    assert!(Json::from_str("\u{feff}\u{feff}1").is_err());
+    assert!(Json::from_str(" \u{feff}1").is_err());
+}
+
+#[test]
+fn test_invalid_utf8_reports_byte_offset() {
+    // "\u{20ac}" is e2 82 ac, the document ends after its first two bytes
+    let err = Json::from_slice(b"{\n  \"price\" : \"\xe2\x82").unwrap_err();
+    assert_eq!(err, ParseError { line: 2, column: 14, kind: ParseErrorKind::InvalidUtf8(15) });
+    assert_eq!(err.to_string(), "invalid UTF-8 sequence at byte 15 at line 2, column 14");
+    let err = Json::from_reader(&b"\xef\xbb\xbf[\"caf\xc3\xa9\", \"\xc3\"]"[..]).unwrap_err();
+    assert_eq!(err, ParseError { line: 1, column: 11, kind: ParseErrorKind::InvalidUtf8(14) });
+    assert_eq!(Json::from_slice(b"[\"caf\xc3\xa9\"]"), Ok(json!( [ "caf\u{e9}" ] )));
+}
+
+#[test]
+fn test_debug_sorts_object_members() {
+    let res = json!( { "width" : 100, "dummy" : { "overview" : true, "b" : null } } );
+    let expected = r#"Object({"dummy": Object({"b": Null, "overview": Boolean(true)}), "width": Integer(100)})"#;
+    for _ in 0..5 {
+        assert_eq!(format!("{:?}", res.clone()), expected);
+    }
+    assert_eq!(format!("{:#?}", json!( { "b" : [ 1.5 ], "a" : "x" } )), "Object(\n    {\n        \"a\": String(\n            \"x\",\n        ),\n        \"b\": Array(\n            [\n                Number(\n                    1.5,\n                ),\n            ],\n        ),\n    },\n)");
+}
+
+#[test]
+fn test_display_scalars() {
+    assert_eq!(json!( null ).to_string(), "null");
+    assert_eq!(json!( true ).to_string(), "true");
+    assert_eq!(json!( 42 ).to_string(), "42");
+    assert_eq!(json!( 2.5 ).to_string(), "2.5");
+    assert_eq!(json!( "yes" ).to_string(), "\"yes\"");
+}
+
+#[test]
+fn test_display_integral_floats() {
+    assert_eq!(Json::Number(1.0).to_string(), "1");
+    assert_eq!(Json::Number(-0.0).to_string(), "-0");
+    assert_eq!(Json::Number(2.5).to_string(), "2.5");
+    assert_eq!(Json::Number(9007199254740992.0).to_string(), "9007199254740992");
+    assert_eq!(Json::Number(1e20).to_string(), "1e20");
+    assert_eq!(Json::Number(-1.5e300).to_string(), "-1.5e300");
+    assert_eq!(Json::Number(1e-5).to_string(), "0.00001");
+    assert_eq!(Json::Number(1e-300).to_string(), "1e-300");
+    assert_eq!(Json::Number(-2.5e-7).to_string(), "-2.5e-7");
+    assert_eq!(Json::Number(5e-324).to_string(), "5e-324");
+    assert_eq!(Json::from_str(&Json::Number(1e20).to_string()), Ok(Json::Number(1e20)));
+    assert_eq!(Json::from_str(&Json::Number(5e-324).to_string()), Ok(Json::Number(5e-324)));
+}
+
+#[test]
+fn test_non_finite_numbers() {
+    let value = json!( { "ok" : 1.5, "bad" : [ (f64::NAN) ] } );
+    assert!(value.to_string().contains("\"bad\":[null]"));
+    assert!(value.try_to_string().unwrap_err().value.is_nan());
+
+    let err = json!( [ 1, (f64::NEG_INFINITY), (f64::INFINITY) ] ).try_to_string().unwrap_err();
+    assert_eq!(err, NonFiniteError { value: f64::NEG_INFINITY });
+    assert_eq!(err.to_string(), "number -inf has no JSON representation");
+    assert_eq!(Json::Number(f64::INFINITY).to_string(), "null");
+    assert_eq!(json!( [ 1, 2.5 ] ).try_to_string(), Ok(String::from("[1,2.5]")));
+}
+
+#[test]
+fn test_display_escapes_strings() {
+    let res = json!( "quote\" back\\ \n\r\t\u{08}\u{0c}\u{01}" );
+    assert_eq!(res.to_string(), r#""quote\" back\\ \n\r\t\b\f\u0001""#);
+}
+
+#[test]
+fn test_serializers_escape_object_keys() {
+    let key = String::from("tab\there");
+    let res = json!( { "a\"b" : 1, (key) : { "back\\slash\n" : "\u{01}" } } );
+    assert_eq!(res["tab\there"].to_string(), r#"{"back\\slash\n":"\u0001"}"#);
+    for text in [ res.to_string(), res.to_pretty_string(2), res.to_canonical_string() ] {
+        assert_eq!(Json::from_str(&text), Ok(res.clone()), "{}", text);
+    }
+    assert_eq!(res.to_canonical_string(), r#"{"a\"b":1,"tab\there":{"back\\slash\n":"\u0001"}}"#);
+}
+
+#[test]
+fn test_display_alternate_pretty_prints() {
+    let res = json!( { "elements" : [ 1, { "overview" : true } ], "empty" : [] } );
+    let element = &res["elements"];
+    assert_eq!(format!("{}", element), r#"[1,{"overview":true}]"#);
+    assert_eq!(format!("{:#}", element), "[\n  1,\n  {\n    \"overview\": true\n  }\n]");
+    assert_eq!(format!("{:#4}", element), element.to_pretty_string(4));
+    assert_eq!(format!("{:#}", res), res.to_pretty_string(2));
+    assert_eq!(format!("{:#}", json!( "s" )), "\"s\"");
+    assert_eq!(format!("{:#}", json!( [] )), "[]");
+}
+
+#[test]
+fn test_coerce_to_string() {
+    assert_eq!(json!( "a \"b\"\n" ).coerce_to_string(), Some(String::from("a \"b\"\n")));
+    assert_eq!(json!( 100 ).coerce_to_string(), Some(String::from("100")));
+    assert_eq!(json!( 480.0 ).coerce_to_string(), Some(String::from("480")));
+    assert_eq!(json!( 1.5 ).coerce_to_string(), Some(String::from("1.5")));
+    assert_eq!(Json::from(f64::NEG_INFINITY).coerce_to_string(), Some(String::from("-inf")));
+    assert_eq!(json!( false ).coerce_to_string(), Some(String::from("false")));
+    assert_eq!(json!( null ).coerce_to_string(), Some(String::from("null")));
+    assert_eq!(json!( [ 1 ] ).coerce_to_string(), None);
+    assert_eq!(json!( {} ).coerce_to_string(), None);
+}
+
+#[test]
+fn test_display_containers() {
+    assert_eq!(json!( [] ).to_string(), "[]");
+    assert_eq!(json!( {} ).to_string(), "{}");
+    assert_eq!(json!( [ 1, [ true, null ], "a" ] ).to_string(), r#"[1,[true,null],"a"]"#);
+    assert_eq!(json!( { "test" : { "tall" : [ 1 ] } } ).to_string(), r#"{"test":{"tall":[1]}}"#);
+}
+
+#[test]
+fn test_display_non_finite_number_is_null() {
+    assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+    assert_eq!(Json::Number(f64::INFINITY).to_string(), "null");
+}
+
+#[test]
+fn test_pretty_nested() {
+    let res = json!( { "test" : [ 1, { "tall" : "yes" } ] } );
+    assert_eq!(res.to_pretty_string(2), "{\n  \"test\": [\n    1,\n    {\n      \"tall\": \"yes\"\n    }\n  ]\n}");
+}
+
+#[test]
+fn test_pretty_empty_containers() {
+    assert_eq!(json!( [] ).to_pretty_string(4), "[]");
+    assert_eq!(json!( {} ).to_pretty_string(4), "{}");
+    assert_eq!(json!( [ [], {} ] ).to_pretty_string(1), "[\n [],\n {}\n]");
+}
+
+#[test]
+fn test_pretty_scalar() {
+    assert_eq!(json!( "a\"b" ).to_pretty_string(4), "\"a\\\"b\"");
+}
+
+#[test]
+fn test_parse_scalars() {
+    assert_eq!(Json::from_str("null"), Ok(Json::Null));
+    assert_eq!(Json::from_str("true"), Ok(Json::Boolean(true)));
+    assert_eq!(Json::from_str("false"), Ok(Json::Boolean(false)));
+    assert_eq!(Json::from_str("-12.5e2"), Ok(Json::Number(-1250.0)));
+    assert_eq!(Json::from_str("0"), Ok(Json::Integer(0)));
+    assert_eq!(Json::from_str(r#""a\"b\\c\/\n""#), Ok(Json::String("a\"b\\c/\n".to_string())));
+}
+
+#[test]
+fn test_parse_nested() {
+    let res = Json::from_str(" { \"width\" : 100, \"elements\" : [ 1, 2, [ {} ] ], \"dummy\" : { \"overview\" : true } } ");
+    assert_eq!(res, Ok(json!( { "width" : 100, "elements" : [ 1, 2, [ {} ] ], "dummy" : { "overview" : true } } )));
+}
+
+#[test]
+fn test_parse_round_trip_display() {
+    let value = json!( { "test" : [ "yes", null, 1.5, { "tall" : false } ] } );
+    assert_eq!(Json::from_str(&value.to_string()), Ok(value.clone()));
+    assert_eq!(Json::from_str(&value.to_pretty_string(2)), Ok(value));
+}
+
+#[test]
+fn test_parse_lenient_numbers() {
+    let lenient = |input| Json::from_str_with_options(input, ParseOptions { lenient_numbers: true, ..ParseOptions::default() });
+    let error = |column, kind| Err(ParseError { line: 1, column, kind });
+    assert_eq!(Json::from_str("+1"), error(1, ParseErrorKind::UnexpectedChar('+')));
+    assert!(matches!(lenient("+1"), Ok(Json::Integer(1))));
+    assert_eq!(Json::from_str(".5"), error(1, ParseErrorKind::UnexpectedChar('.')));
+    assert_eq!(lenient(".5"), Ok(Json::Number(0.5)));
+    assert_eq!(lenient("-.5e1"), Ok(Json::Number(-5.0)));
+    assert_eq!(Json::from_str("1."), error(1, ParseErrorKind::InvalidNumber));
+    assert!(matches!(lenient("1."), Ok(Json::Number(n)) if n == 1.0));
+    assert_eq!(lenient("1.e2"), Ok(Json::Number(100.0)));
+    assert_eq!(Json::from_str("007"), error(1, ParseErrorKind::InvalidNumber));
+    assert_eq!(Json::from_str("01"), error(1, ParseErrorKind::InvalidNumber));
+    assert_eq!(Json::from_str("[ -01 ]"), error(3, ParseErrorKind::InvalidNumber));
+    assert_eq!(Json::from_str("0.5"), Ok(Json::Number(0.5)));
+    assert!(matches!(lenient("007"), Ok(Json::Integer(7))));
+    assert!(matches!(lenient("-00.25"), Ok(Json::Number(n)) if n == -0.25));
+    assert_eq!(lenient("[ +2, 1. ]"), Ok(json!( [ 2, 1.0 ] )));
+
+    for input in [ ".", "+", "-.", "+-1", "+.e1", "1.5." ] {
+        assert!(lenient(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_rejects_invalid_input() {
+    assert!(Json::from_str("").is_err());
+    assert!(Json::from_str("[1, 2").is_err());
+    assert!(Json::from_str("[1, 2,]").is_err());
+    assert!(Json::from_str("{\"a\" 1}").is_err());
+    assert!(Json::from_str("01").is_err());
+    assert!(Json::from_str("1.").is_err());
+    assert!(Json::from_str("nul").is_err());
+    assert!(Json::from_str("\"tab\there\"").is_err());
+}
+
+#[test]
+fn test_parse_rejects_trailing_data() {
+    assert!(Json::from_str("true false").is_err());
+    assert!(Json::from_str("{} x").is_err());
+    assert_eq!(Json::from_str("[1] \n"), Ok(json!( [ 1 ] )));
+}
+
+#[test]
+fn test_parse_error_position() {
+    let err = Json::from_str("{\"a\": }").unwrap_err();
+    assert_eq!(err, ParseError { line: 1, column: 7, kind: ParseErrorKind::UnexpectedChar('}') });
+    assert_eq!(err.to_string(), "unexpected character '}' at line 1, column 7");
+}
+
+#[test]
+fn test_parse_error_position_multiline() {
+    let err = Json::from_str("{\n  \"a\": 1,\n  \"b\": -x\n}").unwrap_err();
+    assert_eq!(err, ParseError { line: 3, column: 8, kind: ParseErrorKind::InvalidNumber });
+}
+
+#[test]
+fn test_parse_error_kinds() {
+    assert_eq!(Json::from_str("[1, 2").unwrap_err().kind, ParseErrorKind::UnexpectedEof);
+    assert_eq!(Json::from_str("\"\\q\"").unwrap_err().kind, ParseErrorKind::InvalidEscape);
+    assert_eq!(Json::from_str("1.e5").unwrap_err().kind, ParseErrorKind::InvalidNumber);
+    assert_eq!(Json::from_str("true false").unwrap_err(), ParseError { line: 1, column: 6, kind: ParseErrorKind::TrailingData });
+}
+
+#[test]
+fn test_normalize() {
+    let mut res = Json::from_str(r#"{"a":2.0,"b":[1.5,-0.0,3,1e2],"c":1e300,"d":"2.0"}"#).unwrap();
+    res.normalize();
+    assert_eq!(res["a"], Json::Integer(2));
+    assert!(matches!(res["b"][0], Json::Number(n) if n == 1.5));
+    assert!(matches!(res["b"][1], Json::Integer(0)));
+    assert!(matches!(res["b"][2], Json::Integer(3)));
+    assert!(matches!(res["b"][3], Json::Integer(100)));
+    assert!(matches!(res["c"], Json::Number(_)));
+    assert_eq!(res["d"], json!( "2.0" ));
+    assert_eq!(res.to_canonical_string(), r#"{"a":2,"b":[1.5e0,0,3,100],"c":1e300,"d":"2.0"}"#);
+    let mut edge = Json::Array(vec![ Json::Number(-9223372036854775808.0), Json::Number(9223372036854775808.0), Json::Number(f64::NAN) ]);
+    edge.normalize();
+    assert!(matches!(edge[0], Json::Integer(i64::MIN)));
+    assert!(matches!(edge[1], Json::Number(_)));
+    assert!(matches!(edge[2], Json::Number(_)));
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_sort_keys() {
+    let mut res = json!( { "width" : 100, "elements" : [ { "b" : 1, "a" : 2 } ], "dummy" : { "z" : true, "B" : 1, "overview" : true } } );
+    res.sort_keys();
+    assert_eq!(res.keys().collect::<Vec<_>>(), [ "dummy", "elements", "width" ]);
+    assert_eq!(res["dummy"].keys().collect::<Vec<_>>(), [ "B", "overview", "z" ]);
+    assert_eq!(res.to_string(), r#"{"dummy":{"B":1,"overview":true,"z":true},"elements":[{"a":2,"b":1}],"width":100}"#);
+}
+
+#[test]
+fn test_map_strings() {
+    let mut doc = json!( { "name" : " moro ", "tags" : [ "a", { "Key" : "b" }, 1 ], "dummy" : { "overview" : true } } );
+    doc.map_strings(str::to_uppercase);
+    assert_eq!(doc, json!( { "name" : " MORO ", "tags" : [ "A", { "Key" : "B" }, 1 ], "dummy" : { "overview" : true } } ));
+    doc.map_strings(|s| s.trim().to_string());
+    assert_eq!(doc["name"], json!( "MORO" ));
+
+    doc.map_keys(str::to_lowercase);
+    assert_eq!(doc, json!( { "name" : "MORO", "tags" : [ "A", { "key" : "B" }, 1 ], "dummy" : { "overview" : true } } ));
+}
+
+#[test]
+fn test_map_values() {
+    let doc = json!( { "name" : "moro", "tags" : [ "a", { "key" : "b" }, 1 ], "width" : 100 } );
+    let upper = doc.clone().map_values(|node| match node {
+        Json::String(s) => Json::String(s.to_uppercase()),
+        other => other
+    });
+    assert_eq!(upper, json!( { "name" : "MORO", "tags" : [ "A", { "key" : "B" }, 1 ], "width" : 100 } ));
+
+    let mut visited = Vec::new();
+    let counted = doc.map_values(|node| {
+        visited.push(node.type_name());
+        match node {
+            Json::Array(elements) => Json::Integer(elements.len() as i64),
+            other => other
+        }
+    });
+    assert_eq!(counted["tags"], json!( 3 ));
+    assert_eq!(visited.len(), 8);
+    assert_eq!(visited.last(), Some(&"object"));
+}
+
+#[test]
+fn test_redact() {
+    let mut res = json!( { "user" : { "name" : "dummy", "auth" : { "password" : "hunter2", "Password" : "x" } }, "tokens" : [ { "token" : 1 } ] } );
+    res.redact(&[ "password", "token" ], json!( "***" ));
+    assert_eq!(res, json!( { "user" : { "name" : "dummy", "auth" : { "password" : "***", "Password" : "x" } }, "tokens" : [ { "token" : "***" } ] } ));
+
+    let mut nested = json!( { "password" : { "password" : 1 } } );
+    nested.redact(&[ "password" ], json!( { "password" : null } ));
+    assert_eq!(nested, json!( { "password" : { "password" : null } } ));
+}
+
+#[test]
+fn test_estimated_size() {
+    let small = json!( { "width" : 100 } );
+    let large = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    let scalar = std::mem::size_of::<Json>();
+    assert_eq!(json!( null ).estimated_size(), scalar);
+    assert_eq!(Json::String(String::with_capacity(64)).estimated_size(), scalar + 64);
+    assert!(json!( [ 1, 2 ] ).estimated_size() >= scalar * 3);
+    assert!(large.estimated_size() > small.estimated_size());
+    assert!(small.estimated_size() > scalar);
+}
+
+#[test]
+fn test_depth() {
+    assert_eq!(json!( 1 ).depth(), 0);
+    assert_eq!(json!( [] ).depth(), 1);
+    assert_eq!(json!( { "a" : [ 1, [ {} ] ], "b" : [] } ).depth(), 4);
+}
+
+#[test]
+fn test_stats() {
+    let desc = json!( { "width" : 100, "height" : 480.0, "elements" : [ 1, 2, 3, 43, 5 ], "dummy" : { "overview" : true } } );
+    let stats = desc.stats();
+    assert_eq!(stats, JsonStats { nulls: 0, booleans: 1, numbers: 7, strings: 0, arrays: 1, objects: 2, nodes: 11, max_depth: 2 });
+    assert_eq!(stats.max_depth, desc.depth());
+    assert_eq!(stats.to_string(), "11 nodes (0 null, 1 boolean, 7 number, 0 string, 1 array, 2 object), max depth 2");
+
+    assert_eq!(json!( null ).stats(), JsonStats { nulls: 1, nodes: 1, ..JsonStats::default() });
+    assert_eq!(json!( [ [ "a", [] ], null ] ).stats().max_depth, 3);
+}
+
+#[test]
+fn test_parse_depth_limit() {
+    let input = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+    assert_eq!(Json::from_str_with_limit(&input, 1000).map(|v| v.depth()), Ok(1000));
+    assert_eq!(Json::from_str_with_limit(&input, 999), Err(ParseError { line: 1, column: 1000, kind: ParseErrorKind::DepthLimitExceeded }));
+    assert_eq!(Json::from_str_with_limit("{ \"a\" : [ 1 ] }", 1).unwrap_err().column, 9);
+    assert_eq!(Json::from_str_with_limit("7", 0), Ok(Json::Integer(7)));
+}
+
+#[test]
+fn test_parse_default_depth_limit() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let limit = ParseOptions::DEFAULT_MAX_DEPTH;
+    assert_eq!(Json::from_str(&nested(limit)).map(|v| v.depth()), Ok(limit));
+    assert_eq!(Json::from_str(&nested(limit + 1)).unwrap_err().kind, ParseErrorKind::DepthLimitExceeded);
+    // Far past anything the stack could take, rejected instead of aborting the process
+    let input = "[".repeat(1_000_000);
+    assert_eq!(Json::from_str(&input).unwrap_err().kind, ParseErrorKind::DepthLimitExceeded);
+    assert_eq!(Json::from_reader(input.as_bytes()).unwrap_err().kind, ParseErrorKind::DepthLimitExceeded);
+    assert_eq!(input.parse::<Json>().unwrap_err().column, limit + 1);
+}
+
+#[test]
+fn test_parse_duplicate_keys() {
+    let input = "{ \"a\" : 1,\n  \"a\" : 2 }";
+    let with = |duplicate_key| Json::from_str_with_options(input, ParseOptions { duplicate_key, ..ParseOptions::default() });
+    assert_eq!(Json::from_str(input), Ok(json!( { "a" : 2 } )));
+    assert_eq!(with(DuplicateKey::UseLast), Ok(json!( { "a" : 2 } )));
+    assert_eq!(with(DuplicateKey::UseFirst), Ok(json!( { "a" : 1 } )));
+    assert_eq!(with(DuplicateKey::Error), Err(ParseError { line: 2, column: 3, kind: ParseErrorKind::DuplicateKey(String::from("a")) }));
+
+    // Keys in different objects don't clash
+    let nested = ParseOptions { duplicate_key: DuplicateKey::Error, ..ParseOptions::default() };
+    assert!(Json::from_str_with_options("{ \"a\" : { \"a\" : 1 } }", nested).is_ok());
+}
+
+#[test]
+fn test_from_str_partial() {
+    let input = "true false 42";
+    let mut rest = input;
+    let mut values = Vec::new();
+    while !rest.trim().is_empty() {
+        let (value, end) = Json::from_str_partial(rest).unwrap();
+        values.push(value);
+        rest = &rest[end..];
+    }
+    assert_eq!(values, [ json!( true ), json!( false ), json!( 42 ) ]);
+    assert_eq!(Json::from_str_partial(" [1][2]"), Ok((json!( [ 1 ] ), 4)));
+    assert_eq!(Json::from_str_partial("{\"a\":1} trailing"), Ok((json!( { "a" : 1 } ), 7)));
+    assert_eq!(Json::from_str_partial("  ").unwrap_err().kind, ParseErrorKind::UnexpectedEof);
+    assert_eq!(Json::from_str_partial("[1,"), Err(ParseError { line: 1, column: 4, kind: ParseErrorKind::UnexpectedEof }));
+}
+
+#[test]
+fn test_parse_multiple() {
+    assert_eq!(Json::parse_multiple("1 2 [3] {}"), Ok(vec![ json!( 1 ), json!( 2 ), json!( [ 3 ] ), json!( {} ) ]));
+    assert_eq!(Json::parse_multiple("{\"a\":1}{\"b\":2}\n[]"), Ok(vec![ json!( { "a" : 1 } ), json!( { "b" : 2 } ), json!( [] ) ]));
+    assert_eq!(Json::parse_multiple(" \n "), Ok(vec![]));
+    assert_eq!(Json::parse_multiple("1 2 ]"), Err(ParseError { line: 1, column: 5, kind: ParseErrorKind::UnexpectedChar(']') }));
+    assert_eq!(Json::parse_multiple("[1]\n[2"), Err(ParseError { line: 2, column: 3, kind: ParseErrorKind::UnexpectedEof }));
+}
+
+#[test]
+fn test_parse_trait() {
+    assert_eq!("[ 1, true ]".parse::<Json>(), Ok(json!( [ 1, true ] )));
+    assert_eq!("[ 1, ".parse::<Json>().unwrap_err().kind, ParseErrorKind::UnexpectedEof);
+    fn parse_all<T: std::str::FromStr>(inputs: &[&str]) -> Vec<Option<T>> {
+        inputs.iter().map(|input| input.parse().ok()).collect()
+    }
+    assert_eq!(parse_all::<Json>(&[ "null", "nul" ]), [ Some(Json::Null), None ]);
+}
+
+#[test]
+fn test_parse_non_finite_tokens() {
+    let options = ParseOptions { allow_nan: true, ..ParseOptions::default() };
+    let res = Json::from_str_with_options("[ NaN, Infinity, -Infinity, -1 ]", options).unwrap();
+    assert!(matches!(res[0], Json::Number(n) if n.is_nan()));
+    assert!(matches!(res[1], Json::Number(n) if n == f64::INFINITY));
+    assert!(matches!(res[2], Json::Number(n) if n == f64::NEG_INFINITY));
+    assert_eq!(res[3], Json::from(-1));
+    assert_eq!(res.try_to_string().unwrap_err().value.to_bits(), f64::NAN.to_bits());
+    assert!(Json::from_str_with_options("-Inf", options).is_err());
+
+    assert_eq!(Json::from_str("NaN"), Err(ParseError { line: 1, column: 1, kind: ParseErrorKind::UnexpectedChar('N') }));
+    assert_eq!(Json::from_str("Infinity").unwrap_err().kind, ParseErrorKind::UnexpectedChar('I'));
+    assert_eq!(Json::from_str("-Infinity").unwrap_err().kind, ParseErrorKind::InvalidNumber);
+}
+
+#[test]
+fn test_parse_number_overflow() {
+    assert_eq!(Json::from_str("[ 1, 1e400 ]"), Err(ParseError { line: 1, column: 6, kind: ParseErrorKind::InvalidNumber }));
+    assert_eq!(Json::from_str("-1.5e309").unwrap_err().kind, ParseErrorKind::InvalidNumber);
+    assert_eq!(Json::from_str("1e-400"), Ok(Json::Number(0.0)));
+    assert_eq!(Json::from_str("1.7976931348623157e308"), Ok(Json::Number(f64::MAX)));
+    let options = ParseOptions { allow_nan: true, ..ParseOptions::default() };
+    assert!(matches!(Json::from_str_with_options("1e400", options), Ok(Json::Number(n)) if n == f64::INFINITY));
+
+    let mut events = Json::events(&b"[ 1, 1e400 ]"[..]);
+    assert_eq!(events.nth(2), Some(Err(ParseError { line: 1, column: 6, kind: ParseErrorKind::InvalidNumber })));
+    assert_eq!(events.next(), None);
+    assert_eq!(Json::events(&b"-1e999"[..]).last(), Some(Err(Json::from_str("-1e999").unwrap_err())));
+}
+
+#[test]
+fn test_parse_size_limits() {
+    let items = |max_items| ParseOptions { max_items, ..ParseOptions::default() };
+    let input = r#"{ "a" : [ 1, 2 ], "b" : {} }"#;
+    assert!(Json::from_str_with_options(input, items(4)).is_ok());
+    assert_eq!(Json::from_str_with_options(input, items(3)), Err(ParseError { line: 1, column: 19, kind: ParseErrorKind::LimitExceeded }));
+    assert_eq!(Json::from_str_with_options("[]", items(0)), Ok(json!( [] )));
+    assert_eq!(Json::from_str_with_options("5", items(0)), Ok(json!( 5 )));
+
+    let strings = |max_string_length| ParseOptions { max_string_length, ..ParseOptions::default() };
+    let input = r#"{ "key" : [ "abc\n" ] }"#;
+    assert!(Json::from_str_with_options(input, strings(4)).is_ok());
+    assert_eq!(Json::from_str_with_options(input, strings(3)), Err(ParseError { line: 1, column: 13, kind: ParseErrorKind::LimitExceeded }));
+    assert_eq!(Json::from_str_with_options(input, strings(2)), Err(ParseError { line: 1, column: 3, kind: ParseErrorKind::LimitExceeded }));
+    assert_eq!(ParseErrorKind::LimitExceeded.to_string(), "size limit exceeded");
+}
+
+#[test]
+fn test_parse_json5_config() {
+    let config = "// service settings\n{\n  \"name\" : \"api\", /* inline */\n  \"ports\" : [ 80, 443, ],\n  \"url\" : \"http://x/*y*/\",\n}\n/* end */";
+    assert_eq!(Json::from_str(config).unwrap_err(), ParseError { line: 1, column: 1, kind: ParseErrorKind::UnexpectedChar('/') });
+    assert_eq!(Json::from_json5(config), Ok(json!( { "name" : "api", "ports" : [ 80, 443 ], "url" : "http://x/*y*/" } )));
+    assert!(Json::from_str("[1,]").is_err());
+}
+
+#[test]
+fn test_parse_json5_errors() {
+    assert_eq!(Json::from_json5("[1] /* open").unwrap_err(), ParseError { line: 1, column: 12, kind: ParseErrorKind::UnexpectedEof });
+    assert_eq!(Json::from_json5("[1] / 2").unwrap_err().kind, ParseErrorKind::UnexpectedChar('/'));
+    assert_eq!(Json::from_json5("[,]").unwrap_err().kind, ParseErrorKind::UnexpectedChar(','));
+    assert_eq!(Json::from_json5("{ \"a\" : 1,, }").unwrap_err().kind, ParseErrorKind::UnexpectedChar(','));
+    assert_eq!(Json::from_json5("[ 1 // one\n, 2 ]"), Ok(json!( [ 1, 2 ] )));
+}
+
+#[test]
+fn test_parse_borrowed() {
+    let input = String::from("{ \"plain\" : \"text\", \"esc\\taped\" : [ \"a\\nb\", 1, null ] }");
+    let value = Json::from_str_borrowed(&input).unwrap();
+    let JsonBorrowed::Object(members) = &value else { panic!("expected an object") };
+    assert!(matches!(members.get("plain"), Some(JsonBorrowed::String(Cow::Borrowed("text")))));
+    assert!(matches!(members.keys().find(|k| k.starts_with("esc")), Some(Cow::Owned(_))));
+    let Some(JsonBorrowed::Array(elements)) = members.get("esc\taped") else { panic!("expected an array") };
+    assert_eq!(elements[0], JsonBorrowed::String(Cow::Owned(String::from("a\nb"))));
+    assert_eq!(value.into_owned(), Json::from_str(&input).unwrap());
+}
+
+#[test]
+fn test_parse_borrowed_errors_and_conversion() {
+    assert_eq!(Json::from_str_borrowed("[1,]"), Err(ParseError { line: 1, column: 4, kind: ParseErrorKind::UnexpectedChar(']') }));
+    let owned = json!( { "a" : [ "x", 2.5 ] } );
+    assert_eq!(JsonBorrowed::from(owned.clone()).into_owned(), owned);
+}
+
+#[test]
+fn test_parse_unicode_escapes() {
+    assert_eq!(Json::from_str(r#""\u0041\u00e9\u20AC""#), Ok(Json::from("Aé€")));
+    assert_eq!(Json::from_str(r#""\uD83D\uDE00""#), Ok(Json::from("😀")));
+    assert_eq!(Json::from_str(r#""x\u0000y""#), Ok(Json::from("x\u{0}y")));
+}
+
+#[test]
+fn test_parse_invalid_unicode_escapes() {
+    let invalid_escape = |line, column| Err(ParseError { line, column, kind: ParseErrorKind::InvalidEscape });
+    assert_eq!(Json::from_str(r#""ab\u00G1""#), invalid_escape(1, 4));
+    assert_eq!(Json::from_str(r#""\u12""#), invalid_escape(1, 2));
+    assert_eq!(Json::from_str(r#""\uD83D""#), invalid_escape(1, 2));
+    assert_eq!(Json::from_str(r#""\uD83D\u0041""#), invalid_escape(1, 2));
+    assert_eq!(Json::from_str("[\n\"ok\", \"\\uDE00\"]"), invalid_escape(2, 8));
+}
+
+#[test]
+fn test_unicode_round_trip() {
+    let value = json!( [ "😀 \u{01} é" ] );
+    assert_eq!(Json::from_str(&value.to_string()), Ok(value));
+}
+
+// Values for the round trip property test: finite numbers only since NaN and infinity have no JSON
+// text, containers are bounded in depth and size so generation terminates quickly
+impl proptest::arbitrary::Arbitrary for Json {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Json>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+        let leaf = prop_oneof![
+            Just(Json::Null),
+            any::<bool>().prop_map(Json::Boolean),
+            any::<f64>().prop_filter("JSON numbers are finite", |n| n.is_finite()).prop_map(Json::Number),
+            any::<i64>().prop_map(Json::Integer),
+            any::<String>().prop_map(Json::String)
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| prop_oneof![
+            proptest::collection::vec(inner.clone(), 0..8).prop_map(Json::Array),
+            proptest::collection::vec((any::<String>(), inner), 0..8).prop_map(|members| members.into_iter().collect())
+        ]).boxed()
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_round_trip_property(value: Json) {
+        proptest::prop_assert_eq!(Json::from_str(&value.to_string()), Ok(value.clone()));
+        proptest::prop_assert_eq!(Json::from_str(&value.to_pretty_string(2)), Ok(value));
+    }
+
+    // Exact bits, not just ==, except that -0.0 comes back as the integer 0
+    #[test]
+    fn test_float_round_trip_property(n in proptest::num::f64::NORMAL | proptest::num::f64::SUBNORMAL | proptest::num::f64::ZERO) {
+        let text = Json::Number(n).to_string();
+        let back = Json::from_str(&text).ok().and_then(|value| value.as_f64());
+        proptest::prop_assert_eq!(back.map(f64::to_bits), Some(if n == 0.0 { 0 } else { n.to_bits() }), "{}", text);
+    }
+}
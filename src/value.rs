@@ -0,0 +1,615 @@
+use std::fmt;
+
+use crate::{remove_member, Json, Map, NonFiniteError};
+
+// Constructors for building values in code without the macro
+impl Json {
+    pub fn null() -> Json {
+        Json::Null
+    }
+
+    pub fn bool(b: bool) -> Json {
+        Json::Boolean(b)
+    }
+
+    /// Always a Json::Number, use Json::from for an integer that should stay exact
+    pub fn number(n: f64) -> Json {
+        Json::Number(n)
+    }
+
+    /// Checked counterpart of number and From<f64>, those accept NaN and infinities (which
+    /// serialize as null) while this rejects them so the value always has a JSON representation
+    pub fn try_number(n: f64) -> Result<Json, NonFiniteError> {
+        if n.is_finite() {
+            Ok(Json::Number(n))
+        } else {
+            Err(NonFiniteError { value: n })
+        }
+    }
+
+    pub fn string(s: impl Into<String>) -> Json {
+        Json::String(s.into())
+    }
+
+    pub fn array(elements: Vec<Json>) -> Json {
+        Json::Array(elements)
+    }
+
+    /// An empty object, members are added with insert
+    pub fn object() -> Json {
+        Json::Object(Map::default())
+    }
+}
+
+// Borrowing accessors, each returns None when the value is of another variant
+impl Json {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            Json::Integer(i) => Some(*i as f64),
+            _ => None
+        }
+    }
+
+    /// The exact value for Json::Integer, a Json::Number only when it is integral and in range,
+    /// so fractions, NaN, infinities and out of range values give None instead of being truncated
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Integer(i) => Some(*i),
+            Json::Number(n) if n.fract() == 0.0 && (-9223372036854775808.0..9223372036854775808.0).contains(n) => Some(*n as i64),
+            _ => None
+        }
+    }
+
+    /// Same rules as as_i64 for the u64 range, negative integers give None
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Integer(i) => u64::try_from(*i).ok(),
+            Json::Number(n) if n.fract() == 0.0 && (0.0..18446744073709551616.0).contains(n) => Some(*n as u64),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Boolean(b) => Some(*b),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(elements) => Some(elements),
+            _ => None
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<String, Json>> {
+        match self {
+            Json::Object(members) => Some(members),
+            _ => None
+        }
+    }
+}
+
+// Variant predicates for quick type checks
+impl Json {
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    #[inline]
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, Json::Boolean(_))
+    }
+
+    #[inline]
+    pub fn is_number(&self) -> bool {
+        matches!(self, Json::Number(_) | Json::Integer(_))
+    }
+
+    #[inline]
+    pub fn is_string(&self) -> bool {
+        matches!(self, Json::String(_))
+    }
+
+    #[inline]
+    pub fn is_array(&self) -> bool {
+        matches!(self, Json::Array(_))
+    }
+
+    #[inline]
+    pub fn is_object(&self) -> bool {
+        matches!(self, Json::Object(_))
+    }
+}
+
+// Navigation into objects by key and arrays by index
+impl Json {
+    pub fn get<'a>(&'a self, key: &str) -> Option<&'a Json> {
+        self.as_object().and_then(|members| members.get(key))
+    }
+
+    pub fn get_index(&self, i: usize) -> Option<&Json> {
+        self.as_array().and_then(|elements| elements.get(i))
+    }
+
+    /// The member 'key' or 'default' when it is missing or self isn't an object
+    pub fn get_or<'a>(&'a self, key: &str, default: &'a Json) -> &'a Json {
+        self.get(key).unwrap_or(default)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        match self {
+            Json::Object(members) => members.get_mut(key),
+            _ => None
+        }
+    }
+
+    pub fn get_index_mut(&mut self, i: usize) -> Option<&mut Json> {
+        match self {
+            Json::Array(elements) => elements.get_mut(i),
+            _ => None
+        }
+    }
+}
+
+// Container helpers for arrays and objects
+impl Json {
+    /// Move the value out and leave Json::Null behind, like Option::take
+    pub fn take(&mut self) -> Json {
+        std::mem::take(self)
+    }
+
+    /// Insert a member and return the previous value for that key, Json::Null is upgraded to an
+    /// empty object first. On any other variant this is a no-op that inserts nothing and returns None
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Json>) -> Option<Json> {
+        if self.is_null() {
+            *self = Json::Object(Map::default());
+        }
+        match self {
+            Json::Object(members) => members.insert(key.into(), value.into()),
+            _ => None
+        }
+    }
+
+    /// Append an element to an array, Json::Null is upgraded to an empty array first. On any
+    /// other variant this is a no-op like insert, nothing is appended
+    pub fn push(&mut self, value: impl Into<Json>) {
+        if self.is_null() {
+            *self = Json::Array(Vec::new());
+        }
+        if let Json::Array(elements) = self {
+            elements.push(value.into());
+        }
+    }
+
+    /// Remove the last element of an array, None when it is empty or self isn't an array
+    pub fn pop(&mut self) -> Option<Json> {
+        match self {
+            Json::Array(elements) => elements.pop(),
+            _ => None
+        }
+    }
+
+    /// Remove a member from an object, None when the key is absent or self isn't an object
+    pub fn remove(&mut self, key: &str) -> Option<Json> {
+        match self {
+            Json::Object(members) => remove_member(members, key),
+            _ => None
+        }
+    }
+
+    /// Entry for a member in place, like HashMap::entry, Json::Null is upgraded to an empty
+    /// object first. Panics when self is any other non-object, the same rule as value["key"] = ...
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        if self.is_null() {
+            *self = Json::Object(Map::default());
+        }
+        match self {
+            Json::Object(members) => Entry { members, key: key.into() },
+            other => panic!("cannot take an entry in non-object JSON value {}", other)
+        }
+    }
+
+    /// Keep only the array elements for which 'f' returns true, like Vec::retain,
+    /// every other variant is left unchanged
+    pub fn retain<F: FnMut(&Json) -> bool>(&mut self, f: F) {
+        if let Json::Array(elements) = self {
+            elements.retain(f);
+        }
+    }
+
+    /// Keep only the object members for which 'f' returns true given key and value,
+    /// every other variant is left unchanged. With preserve_order the kept members stay in order
+    pub fn retain_keys<F: FnMut(&str, &Json) -> bool>(&mut self, mut f: F) {
+        if let Json::Object(members) = self {
+            members.retain(|key, value| f(key, value));
+        }
+    }
+
+    /// Remove every object member whose value is null, at any depth. Null array elements are
+    /// kept since removing them would shift the index of everything after them, objects inside
+    /// arrays are still pruned. A member that only becomes empty, like `{"a": {"b": null}}`, stays
+    pub fn prune_nulls(&mut self) {
+        self.walk_mut(&mut |node| node.retain_keys(|_, value| !value.is_null()));
+    }
+
+    /// Copy of self with the same pruning as prune_nulls, null members are skipped while copying
+    /// rather than cloned and removed afterwards. Null array elements are kept here as well
+    pub fn without_nulls(&self) -> Json {
+        match self {
+            Json::Array(elements) => Json::Array(elements.iter().map(Json::without_nulls).collect()),
+            Json::Object(members) => Json::Object(members.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), value.without_nulls()))
+                .collect()),
+            scalar => scalar.clone()
+        }
+    }
+
+    /// Number of elements or members, None for scalars so "not a container" and "empty" differ
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Json::Array(elements) => Some(elements.len()),
+            Json::Object(members) => Some(members.len()),
+            _ => None
+        }
+    }
+
+    /// True only for an empty array or object, scalars are never empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Members of an object, empty for every other variant
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Json)> {
+        self.as_object().into_iter().flat_map(|members| members.iter())
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = (&String, &mut Json)> {
+        let members = match self {
+            Json::Object(members) => Some(members),
+            _ => None
+        };
+        members.into_iter().flat_map(|members| members.iter_mut())
+    }
+
+    /// True when self is an object with a member named 'key'
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.as_object().is_some_and(|members| members.contains_key(key))
+    }
+
+    /// Member names of an object, empty for every other variant
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.as_object().into_iter().flat_map(|members| members.keys())
+    }
+
+    /// Member values of an object, empty for every other variant
+    pub fn values(&self) -> impl Iterator<Item = &Json> {
+        self.as_object().into_iter().flat_map(|members| members.values())
+    }
+
+    /// Elements of an array, empty for every other variant
+    pub fn iter(&self) -> impl Iterator<Item = &Json> {
+        self.as_array().into_iter().flatten()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Json> {
+        let elements = match self {
+            Json::Array(elements) => Some(elements),
+            _ => None
+        };
+        elements.into_iter().flatten()
+    }
+
+    /// Visit every node depth-first in pre-order: 'f' sees a node before any of its children, array
+    /// elements are visited by index and object members in map iteration order (arbitrary unless
+    /// preserve_order is enabled), each child subtree is finished before the next sibling starts
+    pub fn walk<F: FnMut(&Json)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            Json::Array(elements) => elements.iter().for_each(|element| element.walk(f)),
+            Json::Object(members) => members.values().for_each(|value| value.walk(f)),
+            _ => {}
+        }
+    }
+
+    /// Same visit order as walk, the children are looked up after 'f' returns so a node that
+    /// 'f' replaces is descended into with its new contents
+    pub fn walk_mut<F: FnMut(&mut Json)>(&mut self, f: &mut F) {
+        f(self);
+        match self {
+            Json::Array(elements) => elements.iter_mut().for_each(|element| element.walk_mut(f)),
+            Json::Object(members) => members.values_mut().for_each(|value| value.walk_mut(f)),
+            _ => {}
+        }
+    }
+
+    /// Rewrite every Json::Number that holds an integral value inside the i64 range as
+    /// Json::Integer, recursively. Fractions, non-finite values and integral values outside
+    /// i64 stay Number, and -0.0 becomes Integer(0). Afterwards `2.0` and `2` are the same
+    /// variant, so they serialize identically and compare without the cross-variant path
+    pub fn normalize(&mut self) {
+        self.walk_mut(&mut |node| {
+            if let (Json::Number(_), Some(i)) = (&node, node.as_i64()) {
+                *node = Json::Integer(i);
+            }
+        });
+    }
+
+    /// Replace every string value in the tree with 'f' applied to it, like trimming whitespace.
+    /// Object keys aren't string values and stay as they are, use map_keys for those
+    pub fn map_strings<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        self.walk_mut(&mut |node| {
+            if let Json::String(s) = node {
+                *s = f(s);
+            }
+        });
+    }
+
+    /// Rename every object member in the tree to 'f' applied to its key, like lowercasing keys.
+    /// Values are left alone, when two keys of one object map to the same name only one member is kept
+    pub fn map_keys<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        self.walk_mut(&mut |node| {
+            if let Json::Object(members) = node {
+                *members = std::mem::take(members).into_iter().map(|(key, value)| (f(&key), value)).collect();
+            }
+        });
+    }
+
+    /// Rebuild the tree bottom-up: the children of an array or object are mapped first, then 'f'
+    /// gets the container holding the results, so every node including the root passes through 'f'
+    /// exactly once and what 'f' returns isn't mapped again. Keys are untouched, see map_keys
+    pub fn map_values<F: FnMut(Json) -> Json>(self, mut f: F) -> Json {
+        self.map_values_with(&mut f)
+    }
+
+    fn map_values_with<F: FnMut(Json) -> Json>(self, f: &mut F) -> Json {
+        let mapped = match self {
+            Json::Array(elements) => Json::Array(elements.into_iter().map(|element| element.map_values_with(f)).collect()),
+            Json::Object(members) => Json::Object(members.into_iter().map(|(key, value)| (key, value.map_values_with(f))).collect()),
+            scalar => scalar
+        };
+        f(mapped)
+    }
+
+    /// Replace the value of every object member whose key is exactly one of 'keys' with a copy of
+    /// 'replacement', anywhere in the tree. Replaced values aren't descended into, so neither
+    /// the old value nor the replacement is searched further
+    pub fn redact(&mut self, keys: &[&str], replacement: Json) {
+        self.redact_with(keys, &replacement);
+    }
+
+    fn redact_with(&mut self, keys: &[&str], replacement: &Json) {
+        match self {
+            Json::Array(elements) => elements.iter_mut().for_each(|element| element.redact_with(keys, replacement)),
+            Json::Object(members) => {
+                for (key, value) in members.iter_mut() {
+                    if keys.contains(&key.as_str()) {
+                        *value = replacement.clone();
+                    } else {
+                        value.redact_with(keys, replacement);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Sort the members of every object in the tree by key, in byte order. Only available
+    /// with preserve_order, the default HashMap has no order that sorting could fix
+    #[cfg(feature = "preserve_order")]
+    pub fn sort_keys(&mut self) {
+        self.walk_mut(&mut |node| {
+            if let Json::Object(members) = node {
+                members.sort_keys();
+            }
+        });
+    }
+
+    /// Rough number of bytes the value occupies in memory: the Json itself plus string and vector
+    /// capacities and one key and value slot per map capacity, recursively. Allocator and hash table
+    /// bookkeeping isn't counted, so treat it as an estimate that grows with the document
+    pub fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Json>() + self.heap_size()
+    }
+
+    // Bytes owned on the heap, not counting the Json itself which lives in its parent's slot
+    fn heap_size(&self) -> usize {
+        match self {
+            Json::String(s) => s.capacity(),
+            Json::Array(elements) => {
+                elements.capacity() * std::mem::size_of::<Json>() + elements.iter().map(Json::heap_size).sum::<usize>()
+            },
+            Json::Object(members) => {
+                let slots = members.capacity() * (std::mem::size_of::<String>() + std::mem::size_of::<Json>());
+                slots + members.iter().map(|(key, value)| key.capacity() + value.heap_size()).sum::<usize>()
+            },
+            _ => 0
+        }
+    }
+
+    /// Maximum number of arrays and objects nested inside each other, 0 for scalars,
+    /// so `[]` is 1 and `{"a": [1]}` is 2, the same count from_str_with_limit checks against
+    pub fn depth(&self) -> usize {
+        let children = match self {
+            Json::Array(elements) => elements.iter().map(Json::depth).max(),
+            Json::Object(members) => members.values().map(Json::depth).max(),
+            _ => return 0
+        };
+        children.unwrap_or(0) + 1
+    }
+
+    /// Count the nodes of every variant and measure the depth in one traversal, to get a feel for
+    /// an unknown document. Integer and Number both count as numbers, object keys aren't nodes
+    pub fn stats(&self) -> JsonStats {
+        let mut stats = JsonStats::default();
+        self.collect_stats(&mut stats, 0);
+        stats
+    }
+
+    fn collect_stats(&self, stats: &mut JsonStats, level: usize) {
+        stats.nodes += 1;
+        match self {
+            Json::Null => stats.nulls += 1,
+            Json::Boolean(_) => stats.booleans += 1,
+            Json::Number(_) | Json::Integer(_) => stats.numbers += 1,
+            Json::String(_) => stats.strings += 1,
+            Json::Array(elements) => {
+                stats.arrays += 1;
+                stats.max_depth = stats.max_depth.max(level + 1);
+                elements.iter().for_each(|element| element.collect_stats(stats, level + 1));
+            },
+            Json::Object(members) => {
+                stats.objects += 1;
+                stats.max_depth = stats.max_depth.max(level + 1);
+                members.values().for_each(|value| value.collect_stats(stats, level + 1));
+            }
+        }
+    }
+}
+
+/// Node counts of a document as returned by Json::stats, 'nodes' is the sum of the per variant
+/// counts and 'max_depth' counts nested arrays and objects the same way Json::depth does
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct JsonStats {
+    pub nulls: usize,
+    pub booleans: usize,
+    pub numbers: usize,
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    pub nodes: usize,
+    pub max_depth: usize
+}
+
+impl fmt::Display for JsonStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} nodes ({} null, {} boolean, {} number, {} string, {} array, {} object), max depth {}",
+            self.nodes, self.nulls, self.booleans, self.numbers, self.strings, self.arrays, self.objects, self.max_depth)
+    }
+}
+
+/// A member slot of an object that may or may not be occupied, created by Json::entry
+pub struct Entry<'a> {
+    members: &'a mut Map<String, Json>,
+    key: String
+}
+
+impl<'a> Entry<'a> {
+    /// Key this entry was created for
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Insert 'default' when the member is absent, then return the member
+    pub fn or_insert(self, default: impl Into<Json>) -> &'a mut Json {
+        self.members.entry(self.key).or_insert(default.into())
+    }
+
+    /// Like or_insert, 'default' is only called when the member is absent
+    pub fn or_insert_with<F: FnOnce() -> Json>(self, default: F) -> &'a mut Json {
+        self.members.entry(self.key).or_insert_with(default)
+    }
+
+    /// Run 'f' on the member when it is present, absent members are left for or_insert
+    pub fn and_modify<F: FnOnce(&mut Json)>(self, f: F) -> Self {
+        if let Some(value) = self.members.get_mut(&self.key) {
+            f(value);
+        }
+        self
+    }
+}
+
+/// Fluent construction of an object when members depend on runtime conditions, where the json!
+/// macro would need a mutable temporary. Setting a key twice keeps the later value
+///
+/// ```
+/// use json_moro::{json, JsonBuilder};
+///
+/// let verbose = false;
+/// let doc = JsonBuilder::new().set("width", 100).set_if(verbose, "debug", true).build();
+/// assert_eq!(doc, json!( { "width" : 100 } ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonBuilder {
+    members: Map<String, Json>
+}
+
+impl JsonBuilder {
+    /// Builder for an empty object
+    pub fn new() -> JsonBuilder {
+        JsonBuilder::default()
+    }
+
+    /// Add the member 'key', replacing an earlier value for the same key
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<Json>) -> JsonBuilder {
+        self.members.insert(key.into(), value.into());
+        self
+    }
+
+    /// Like set when 'condition' holds, otherwise the builder is returned unchanged
+    pub fn set_if(self, condition: bool, key: impl Into<String>, value: impl Into<Json>) -> JsonBuilder {
+        if condition { self.set(key, value) } else { self }
+    }
+
+    /// The object with every member set so far, with preserve_order in the order they were first set
+    pub fn build(self) -> Json {
+        Json::Object(self.members)
+    }
+}
+
+// Shared sentinel handed out by indexing when nothing is found
+static NULL: Json = Json::Null;
+
+// value["key"] never panics, a missing key or a value that isn't an object gives Json::Null,
+// so chained lookups like value["a"]["b"] are safe on absent paths
+impl std::ops::Index<&str> for Json {
+    type Output = Json;
+
+    fn index(&self, key: &str) -> &Json {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+// value[i] follows the same rule, out of range or not an array gives Json::Null
+impl std::ops::Index<usize> for Json {
+    type Output = Json;
+
+    fn index(&self, i: usize) -> &Json {
+        self.get_index(i).unwrap_or(&NULL)
+    }
+}
+
+// value["key"] = ... inserts Json::Null for a missing key before handing out the slot,
+// it panics when the value isn't an object since there is nowhere to put the member
+impl std::ops::IndexMut<&str> for Json {
+    fn index_mut(&mut self, key: &str) -> &mut Json {
+        match self {
+            Json::Object(members) => members.entry(key.to_string()).or_insert(Json::Null),
+            other => panic!("cannot index into non-object JSON value {} with key {:?}", other, key)
+        }
+    }
+}
+
+// value[i] = ... only replaces existing elements, it panics when out of range or not an array
+impl std::ops::IndexMut<usize> for Json {
+    fn index_mut(&mut self, i: usize) -> &mut Json {
+        match self {
+            Json::Array(elements) => {
+                let len = elements.len();
+                elements.get_mut(i).unwrap_or_else(|| panic!("index {} out of range for JSON array of length {}", i, len))
+            },
+            other => panic!("cannot index into non-array JSON value {} with index {}", other, i)
+        }
+    }
+}
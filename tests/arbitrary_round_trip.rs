@@ -0,0 +1,26 @@
+// The body of a fuzz target for the 'arbitrary' feature, driven here by pseudo-random bytes.
+// With cargo-fuzz the same function goes into fuzz_target!(|value: Json| round_trip(value))
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use json_moro::Json;
+
+fn round_trip(value: Json) {
+    assert_eq!(Json::from_str(&value.to_string()), Ok(value.clone()));
+    assert_eq!(Json::from_str(&value.to_pretty_string(2)), Ok(value));
+}
+
+#[test]
+fn test_arbitrary_round_trip() {
+    let mut state = 0x2545F4914F6CDD1Du64;
+    for _ in 0..200 {
+        let bytes: Vec<u8> = (0..4096).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect();
+        let mut u = Unstructured::new(&bytes);
+        round_trip(Json::arbitrary(&mut u).unwrap());
+    }
+}
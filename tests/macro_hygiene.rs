@@ -0,0 +1,22 @@
+// Using the exported json macro from a downstream crate that doesn't import Json,
+// the macro is invoked through its exported path
+
+// A local type with the same name must not be picked up by the macro expansion
+#[allow(dead_code)]
+struct Json;
+
+#[test]
+fn test_macro_outside_defining_crate() {
+    let width = 100;
+    let key = "dummy";
+    let res = json_moro::json!( { "width" : width, "elements" : [ 1, null, "x" ], (key) : { "overview" : true } } );
+    let expected = json_moro::Json::Object(json_moro::Map::from([
+        (String::from("width"), json_moro::Json::Integer(100)),
+        (String::from("elements"), json_moro::Json::Array(vec![ json_moro::Json::Integer(1), json_moro::Json::Null, json_moro::Json::String(String::from("x")) ])),
+        (String::from("dummy"), json_moro::Json::Object(json_moro::Map::from([ (String::from("overview"), json_moro::Json::Boolean(true)) ])))
+    ]));
+    assert_eq!(res, expected);
+    assert_eq!(res["elements"][2], json_moro::Json::String(String::from("x")));
+    assert_eq!(res["dummy"]["overview"], json_moro::Json::Boolean(true));
+    assert_eq!(json_moro::json!( null ), json_moro::Json::Null);
+}